@@ -1,17 +1,22 @@
 //! 4.1.3.  Address Literals (RFC 5321)
 
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    str::from_utf8,
+};
+
 use abnf_core::streaming::is_DIGIT;
 use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case, take_while1, take_while_m_n},
     character::is_hex_digit,
-    combinator::{map_res, opt, recognize},
+    combinator::{map, map_opt, map_res, not, opt, verify},
     multi::{count, many_m_n},
-    sequence::{delimited, tuple},
+    sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
 
-use crate::parse::ldh_str;
+use crate::{parse::ldh_str, AddressLiteral, GeneralAddressLiteral};
 
 /// address-literal = "[" (
 ///                       IPv4-address-literal /
@@ -19,103 +24,108 @@ use crate::parse::ldh_str;
 ///                       General-address-literal
 ///                   ) "]"
 ///                     ; See Section 4.1.3
-pub fn address_literal(input: &[u8]) -> IResult<&[u8], &str> {
+pub fn address_literal(input: &[u8]) -> IResult<&[u8], AddressLiteral> {
     delimited(
         tag(b"["),
-        map_res(
-            alt((
-                ipv4_address_literal,
-                ipv6_address_literal,
-                general_address_literal,
-            )),
-            std::str::from_utf8,
-        ),
+        alt((
+            map(ipv4_address_literal, AddressLiteral::V4),
+            map(ipv6_address_literal, AddressLiteral::V6),
+            map_opt(general_address_literal, |(tag, content)| {
+                GeneralAddressLiteral::new(tag, content)
+                    .ok()
+                    .map(AddressLiteral::General)
+            }),
+        )),
         tag(b"]"),
     )(input)
 }
 
 /// IPv4-address-literal = Snum 3("."  Snum)
-pub fn ipv4_address_literal(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((snum, count(tuple((tag(b"."), snum)), 3)));
+pub fn ipv4_address_literal(input: &[u8]) -> IResult<&[u8], Ipv4Addr> {
+    let mut parser = tuple((snum, count(preceded(tag(b"."), snum), 3)));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let (remaining, (a, rest)) = parser(input)?;
 
-    Ok((remaining, parsed))
+    Ok((remaining, Ipv4Addr::new(a, rest[0], rest[1], rest[2])))
 }
 
 /// Representing a decimal integer value in the range 0 through 255
 ///
 /// Snum = 1*3DIGIT
-pub fn snum(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    take_while_m_n(1, 3, is_DIGIT)(input)
+pub fn snum(input: &[u8]) -> IResult<&[u8], u8> {
+    map_res(
+        map_res(take_while_m_n(1, 3, is_DIGIT), from_utf8),
+        str::parse::<u8>,
+    )(input)
 }
 
 /// IPv6-address-literal = "IPv6:" IPv6-addr
-pub fn ipv6_address_literal(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((tag_no_case(b"IPv6:"), ipv6_addr));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn ipv6_address_literal(input: &[u8]) -> IResult<&[u8], Ipv6Addr> {
+    preceded(tag_no_case(b"IPv6:"), ipv6_addr)(input)
 }
 
 /// IPv6-addr = IPv6-full / IPv6-comp / IPv6v4-full / IPv6v4-comp
-pub fn ipv6_addr(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = alt((ipv6_full, ipv6_comp, ipv6v4_full, ipv6v4_comp));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn ipv6_addr(input: &[u8]) -> IResult<&[u8], Ipv6Addr> {
+    // Note: The IPv6v4 alternatives must be tried before IPv6-comp. Otherwise, IPv6-comp
+    // matches the groups in front of the IPv4-address-literal, e.g., "::ffff" in
+    // "::ffff:192.0.2.1", and the address literal is rejected.
+    alt((ipv6_full, ipv6v4_full, ipv6v4_comp, ipv6_comp))(input)
 }
 
 /// IPv6-full = IPv6-hex 7(":" IPv6-hex)
-pub fn ipv6_full(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((ipv6_hex, count(tuple((tag(b":"), ipv6_hex)), 7)));
+pub fn ipv6_full(input: &[u8]) -> IResult<&[u8], Ipv6Addr> {
+    let mut parser = tuple((ipv6_hex, count(preceded(tag(b":"), ipv6_hex), 7)));
+
+    let (remaining, (first, rest)) = parser(input)?;
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let mut groups = vec![first];
+    groups.extend(rest);
 
-    Ok((remaining, parsed))
+    Ok((remaining, expand(&groups, &[])))
 }
 
 /// IPv6-hex = 1*4HEXDIG
-pub fn ipv6_hex(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    take_while_m_n(1, 4, is_hex_digit)(input)
+///
+/// Note: A group followed by "." is the start of an IPv4-address-literal and not an IPv6-hex.
+pub fn ipv6_hex(input: &[u8]) -> IResult<&[u8], u16> {
+    terminated(
+        map_res(
+            map_res(take_while_m_n(1, 4, is_hex_digit), from_utf8),
+            |hex| u16::from_str_radix(hex, 16),
+        ),
+        not(tag(b".")),
+    )(input)
 }
 
 /// The "::" represents at least 2 16-bit groups of zeros.
 /// No more than 6 groups in addition to the "::" may be present.
 ///
 /// IPv6-comp = [IPv6-hex *5(":" IPv6-hex)] "::" [IPv6-hex *5(":" IPv6-hex)]
-pub fn ipv6_comp(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((
-        opt(tuple((
-            ipv6_hex,
-            many_m_n(0, 5, tuple((tag(b":"), ipv6_hex))),
-        ))),
-        tag(b"::"),
-        opt(tuple((
-            ipv6_hex,
-            many_m_n(0, 5, tuple((tag(b":"), ipv6_hex))),
-        ))),
-    ));
+pub fn ipv6_comp(input: &[u8]) -> IResult<&[u8], Ipv6Addr> {
+    let parser = tuple((ipv6_groups(5), tag(b"::"), ipv6_groups(5)));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let (remaining, (head, _, tail)) =
+        verify(parser, |(head, _, tail)| head.len() + tail.len() <= 6)(input)?;
 
-    Ok((remaining, parsed))
+    Ok((remaining, expand(&head, &tail)))
 }
 
 /// IPv6v4-full = IPv6-hex 5(":" IPv6-hex) ":" IPv4-address-literal
-pub fn ipv6v4_full(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((
+pub fn ipv6v4_full(input: &[u8]) -> IResult<&[u8], Ipv6Addr> {
+    let mut parser = tuple((
         ipv6_hex,
-        count(tuple((tag(b":"), ipv6_hex)), 5),
+        count(preceded(tag(b":"), ipv6_hex), 5),
         tag(b":"),
         ipv4_address_literal,
     ));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let (remaining, (first, rest, _, ipv4)) = parser(input)?;
 
-    Ok((remaining, parsed))
+    let mut groups = vec![first];
+    groups.extend(rest);
+    groups.extend(ipv4_groups(ipv4));
+
+    Ok((remaining, expand(&groups, &[])))
 }
 
 /// The "::" represents at least 2 16-bit groups of zeros.
@@ -124,33 +134,80 @@ pub fn ipv6v4_full(input: &[u8]) -> IResult<&[u8], &[u8]> {
 /// IPv6v4-comp = [IPv6-hex *3(":" IPv6-hex)] "::"
 ///               [IPv6-hex *3(":" IPv6-hex) ":"]
 ///               IPv4-address-literal
-pub fn ipv6v4_comp(input: &[u8]) -> IResult<&[u8], &[u8]> {
+pub fn ipv6v4_comp(input: &[u8]) -> IResult<&[u8], Ipv6Addr> {
     let parser = tuple((
-        opt(tuple((
-            ipv6_hex,
-            many_m_n(0, 3, tuple((tag(b":"), ipv6_hex))),
-        ))),
+        ipv6_groups(3),
         tag(b"::"),
-        opt(tuple((
-            ipv6_hex,
-            many_m_n(0, 3, tuple((tag(b":"), ipv6_hex))),
-            tag(b":"),
-        ))),
+        map(
+            opt(terminated(ipv6_groups(3), tag(b":"))),
+            Option::unwrap_or_default,
+        ),
         ipv4_address_literal,
     ));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let (remaining, (head, _, mut tail, ipv4)) =
+        verify(parser, |(head, _, tail, _)| head.len() + tail.len() <= 4)(input)?;
+
+    tail.extend(ipv4_groups(ipv4));
 
-    Ok((remaining, parsed))
+    Ok((remaining, expand(&head, &tail)))
+}
+
+/// [IPv6-hex *max(":" IPv6-hex)]
+fn ipv6_groups(max: usize) -> impl FnMut(&[u8]) -> IResult<&[u8], Vec<u16>> {
+    move |input| {
+        let mut parser = opt(tuple((
+            ipv6_hex,
+            many_m_n(0, max, preceded(tag(b":"), ipv6_hex)),
+        )));
+
+        let (remaining, groups) = parser(input)?;
+
+        let groups = match groups {
+            Some((first, rest)) => {
+                let mut groups = vec![first];
+                groups.extend(rest);
+                groups
+            }
+            None => vec![],
+        };
+
+        Ok((remaining, groups))
+    }
+}
+
+/// An IPv4-address-literal represents the last two 16-bit groups of an IPv6 address.
+fn ipv4_groups(ipv4: Ipv4Addr) -> [u16; 2] {
+    let [a, b, c, d] = ipv4.octets();
+
+    [u16::from_be_bytes([a, b]), u16::from_be_bytes([c, d])]
+}
+
+/// Fill the groups between `head` and `tail` with zeros.
+fn expand(head: &[u16], tail: &[u16]) -> Ipv6Addr {
+    let mut groups = [0u16; 8];
+
+    groups[..head.len()].copy_from_slice(head);
+    groups[8 - tail.len()..].copy_from_slice(tail);
+
+    Ipv6Addr::from(groups)
 }
 
 /// General-address-literal = Standardized-tag ":" 1*dcontent
-pub fn general_address_literal(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((standardized_tag, tag(b":"), take_while1(is_dcontent)));
+///
+/// Note: An invalid IPv6-address-literal is not accepted as a General-address-literal.
+pub fn general_address_literal(input: &[u8]) -> IResult<&[u8], (&str, &str)> {
+    let mut parser = tuple((
+        verify(map_res(standardized_tag, from_utf8), |tag: &str| {
+            !tag.eq_ignore_ascii_case("IPv6")
+        }),
+        tag(b":"),
+        map_res(take_while1(is_dcontent), from_utf8),
+    ));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let (remaining, (tag, _, content)) = parser(input)?;
 
-    Ok((remaining, parsed))
+    Ok((remaining, (tag, content)))
 }
 
 /// Standardized-tag MUST be specified in a Standards-Track RFC and registered with IANA
@@ -166,3 +223,90 @@ pub fn standardized_tag(input: &[u8]) -> IResult<&[u8], &[u8]> {
 pub fn is_dcontent(byte: u8) -> bool {
     matches!(byte, 33..=90 | 94..=126)
 }
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::address_literal;
+    use crate::{AddressLiteral, GeneralAddressLiteral};
+
+    #[test]
+    fn test_address_literal() {
+        let v6 = |address: &str| AddressLiteral::V6(address.parse::<Ipv6Addr>().unwrap());
+
+        let tests: &[(&[u8], AddressLiteral)] = &[
+            (b"[0.0.0.0]", AddressLiteral::V4(Ipv4Addr::UNSPECIFIED)),
+            (
+                b"[192.0.2.1]",
+                AddressLiteral::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            ),
+            (
+                b"[192.000.002.001]",
+                AddressLiteral::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            ),
+            (b"[IPv6:1:2:3:4:5:6:7:8]", v6("1:2:3:4:5:6:7:8")),
+            (b"[ipv6:2001:DB8::1]", v6("2001:db8::1")),
+            (b"[IPv6:::]", v6("::")),
+            (b"[IPv6:::1]", v6("::1")),
+            (b"[IPv6:1::]", v6("1::")),
+            (b"[IPv6:1:2:3::4:5:6]", v6("1:2:3::4:5:6")),
+            (b"[IPv6:1:2:3:4:5:6:192.0.2.1]", v6("1:2:3:4:5:6:192.0.2.1")),
+            (b"[IPv6:::ffff:192.0.2.1]", v6("::ffff:192.0.2.1")),
+            (b"[IPv6:::192.0.2.1]", v6("::192.0.2.1")),
+            (b"[IPv6:1:2::3:4:192.0.2.1]", v6("1:2::3:4:192.0.2.1")),
+            (
+                b"[x-tag:some-content]",
+                AddressLiteral::General(
+                    GeneralAddressLiteral::new("x-tag", "some-content").unwrap(),
+                ),
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, got) = address_literal(test).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(got, *expected);
+        }
+
+        let tests: &[&[u8]] = &[
+            b"[999.0.0.1]",
+            b"[256.0.0.1]",
+            b"[1.2.3]",
+            b"[1.2.3.4.5]",
+            b"[IPv6:1:2:3:4:5:6:7]",
+            b"[IPv6:1:2:3:4:5:6:7:8:9]",
+            b"[IPv6:1:2:3:4:5:6:7::]",
+            b"[IPv6:1:2:3::4:5:6:7]",
+            b"[IPv6:1::2::3]",
+            b"[IPv6:12345::]",
+            b"[IPv6:1:2:3:4:5::192.0.2.1]",
+            b"[IPv6:::999.0.2.1]",
+            b"[IPv6:example]",
+            b"[x-tag:]",
+        ];
+
+        for test in tests {
+            assert!(address_literal(test).is_err(), "{:?}", test);
+        }
+    }
+
+    #[test]
+    fn test_address_literal_canonical() {
+        let tests: &[(&[u8], &str)] = &[
+            (b"[192.000.002.001]", "[192.0.2.1]"),
+            (b"[ipv6:2001:DB8:0:0:0:0:0:1]", "[IPv6:2001:db8::1]"),
+            (
+                b"[IPv6:0:0:0:0:0:ffff:192.0.2.1]",
+                "[IPv6:::ffff:192.0.2.1]",
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let (_, got) = address_literal(test).unwrap();
+            let mut out = Vec::new();
+            got.serialize(&mut out).unwrap();
+            assert_eq!(out, expected.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! Message Header Field for Indicating Message Authentication Status (RFC 8601)
+
+use abnf_core::streaming::CRLF;
+use nom::{
+    branch::alt,
+    bytes::streaming::{tag, tag_no_case, take_while1},
+    combinator::{map, map_res, opt, recognize},
+    multi::many1,
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::{
+    auth_results::{AuthResult, AuthenticationResults, Method, MethodResult, Property},
+    parse::{
+        imf::{
+            addr_spec::{domain_content, local_part_content},
+            folding_ws_and_comment::cfws,
+            quoted_strings::quoted_string_content,
+        },
+        number, sub_domain,
+        utils::{has_field_name, header_fields},
+    },
+};
+
+/// authres-header-field = "Authentication-Results:" authres-payload
+pub fn authres_header_field(input: &[u8]) -> IResult<&[u8], AuthenticationResults> {
+    preceded(tag_no_case(b"Authentication-Results:"), authres_payload)(input)
+}
+
+/// authres-payload = [CFWS] authserv-id
+///                   [ CFWS authres-version ]
+///                   ( no-result / 1*resinfo ) [CFWS] CRLF
+pub fn authres_payload(input: &[u8]) -> IResult<&[u8], AuthenticationResults> {
+    let mut parser = tuple((
+        preceded(opt(cfws), authserv_id),
+        opt(preceded(cfws, authres_version)),
+        alt((map(no_result, |_| vec![]), many1(resinfo))),
+        opt(cfws),
+        CRLF,
+    ));
+
+    let (remaining, (authserv_id, version, results, _, _)) = parser(input)?;
+
+    Ok((
+        remaining,
+        AuthenticationResults {
+            authserv_id,
+            version,
+            results,
+        },
+    ))
+}
+
+/// authserv-id = value
+pub fn authserv_id(input: &[u8]) -> IResult<&[u8], String> {
+    value(input)
+}
+
+/// authres-version = 1*DIGIT [CFWS]
+pub fn authres_version(input: &[u8]) -> IResult<&[u8], u32> {
+    terminated(number, opt(cfws))(input)
+}
+
+/// no-result = [CFWS] ";" [CFWS] "none"
+pub fn no_result(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let parser = tuple((opt(cfws), tag(b";"), opt(cfws), tag_no_case(b"none")));
+
+    let (remaining, parsed) = recognize(parser)(input)?;
+
+    Ok((remaining, parsed))
+}
+
+/// resinfo = [CFWS] ";" methodspec [ CFWS reasonspec ] [ CFWS 1*propspec ]
+///
+/// Note: The CFWS before reasonspec and propspec is optional here because a quoted-string
+///       already consumes trailing CFWS.
+pub fn resinfo(input: &[u8]) -> IResult<&[u8], MethodResult> {
+    let mut parser = tuple((
+        opt(cfws),
+        tag(b";"),
+        methodspec,
+        opt(preceded(opt(cfws), reasonspec)),
+        opt(preceded(opt(cfws), many1(propspec))),
+    ));
+
+    let (remaining, (_, _, (method, method_version, result), reason, properties)) = parser(input)?;
+
+    Ok((
+        remaining,
+        MethodResult {
+            method,
+            method_version,
+            result,
+            reason,
+            properties: properties.unwrap_or_default(),
+        },
+    ))
+}
+
+/// methodspec = [CFWS] method [CFWS] "=" [CFWS] result
+pub fn methodspec(input: &[u8]) -> IResult<&[u8], (Method, Option<u32>, AuthResult)> {
+    let mut parser = tuple((
+        opt(cfws),
+        method,
+        opt(cfws),
+        tag(b"="),
+        opt(cfws),
+        map(keyword, AuthResult::from),
+    ));
+
+    let (remaining, (_, (method, version), _, _, _, result)) = parser(input)?;
+
+    Ok((remaining, (method, version, result)))
+}
+
+/// method = Keyword [ [CFWS] "/" [CFWS] method-version ]
+///
+/// method-version = 1*DIGIT [CFWS]
+pub fn method(input: &[u8]) -> IResult<&[u8], (Method, Option<u32>)> {
+    let mut parser = tuple((
+        map(keyword, Method::from),
+        opt(preceded(
+            tuple((opt(cfws), tag(b"/"), opt(cfws))),
+            terminated(number, opt(cfws)),
+        )),
+    ));
+
+    let (remaining, (method, version)) = parser(input)?;
+
+    Ok((remaining, (method, version)))
+}
+
+/// reasonspec = "reason" [CFWS] "=" [CFWS] value
+pub fn reasonspec(input: &[u8]) -> IResult<&[u8], String> {
+    preceded(
+        tuple((tag_no_case(b"reason"), opt(cfws), tag(b"="), opt(cfws))),
+        value,
+    )(input)
+}
+
+/// propspec = ptype [CFWS] "." [CFWS] property [CFWS] "=" pvalue
+///
+/// ptype = Keyword
+///
+/// property = special-smtp-verb / Keyword
+///
+/// special-smtp-verb = "mailfrom" / "rcptto"
+pub fn propspec(input: &[u8]) -> IResult<&[u8], Property> {
+    let mut parser = tuple((
+        keyword,
+        opt(cfws),
+        tag(b"."),
+        opt(cfws),
+        keyword,
+        opt(cfws),
+        tag(b"="),
+        pvalue,
+    ));
+
+    let (remaining, (ptype, _, _, _, property, _, _, value)) = parser(input)?;
+
+    Ok((
+        remaining,
+        Property {
+            ptype: ptype.to_owned(),
+            property: property.to_owned(),
+            value,
+        },
+    ))
+}
+
+/// pvalue = [CFWS] ( value / [ [ local-part ] "@" ] domain-name ) [CFWS]
+///
+/// Note: Addresses are tried first because a token is a prefix of an address.
+pub fn pvalue(input: &[u8]) -> IResult<&[u8], String> {
+    let address = map(
+        tuple((opt(local_part_content), tag(b"@"), domain_content)),
+        |(local_part, _, domain)| format!("{}@{}", local_part.unwrap_or_default(), domain),
+    );
+
+    delimited(opt(cfws), alt((address, value)), opt(cfws))(input)
+}
+
+/// value = token / quoted-string (RFC 2045)
+pub fn value(input: &[u8]) -> IResult<&[u8], String> {
+    alt((
+        map(map_res(token, std::str::from_utf8), str::to_owned),
+        quoted_string_content,
+    ))(input)
+}
+
+/// token = 1*<any (US-ASCII) CHAR except SPACE, CTLs, or tspecials>
+pub fn token(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|byte| matches!(byte, 33..=126) && !is_tspecial(byte))(input)
+}
+
+/// tspecials = "(" / ")" / "<" / ">" / "@" / "," / ";" / ":" / "\" / <"> / "/" / "[" / "]" /
+///             "?" / "="
+pub fn is_tspecial(byte: u8) -> bool {
+    b"()<>@,;:\\\"/[]?=".contains(&byte)
+}
+
+/// Keyword = Ldh-str
+///
+/// Note: Keywords start with a letter or digit, i.e., this is the same as sub-domain.
+pub fn keyword(input: &[u8]) -> IResult<&[u8], &str> {
+    map_res(sub_domain, std::str::from_utf8)(input)
+}
+
+/// Extract all "Authentication-Results:" fields from a header section.
+///
+/// Fields are returned in header order. Malformed fields are skipped.
+///
+/// Caution: Only results of trusted authentication services should be used, see
+///          [`AuthenticationResults::is_from`].
+///
+/// Note: Lines must be terminated by CRLF.
+pub fn authentication_results(header: &[u8]) -> Vec<AuthenticationResults> {
+    header_fields(header)
+        .into_iter()
+        .filter(|field| has_field_name(field, b"Authentication-Results"))
+        .filter_map(|field| {
+            // The empty line makes sure that the streaming parser does not wait for more
+            // folded lines.
+            let input = [field, b"\r\n"].concat();
+
+            match authres_header_field(&input) {
+                Ok((b"\r\n", results)) => Some(results),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{authentication_results, authres_header_field, pvalue};
+    use crate::auth_results::{AuthResult, AuthenticationResults, Method, MethodResult, Property};
+
+    #[test]
+    fn test_authres_header_field() {
+        // RFC 8601, Appendix B
+        let tests: &[(&[u8], AuthenticationResults)] = &[
+            (
+                b"Authentication-Results: example.org 1; none\r\n\r\n",
+                AuthenticationResults {
+                    authserv_id: "example.org".into(),
+                    version: Some(1),
+                    results: vec![],
+                },
+            ),
+            (
+                b"Authentication-Results: example.com;\r\n\
+                  \x20         spf=pass smtp.mailfrom=example.net\r\n\r\n",
+                AuthenticationResults {
+                    authserv_id: "example.com".into(),
+                    version: None,
+                    results: vec![MethodResult::new(Method::Spf, AuthResult::Pass).property(
+                        "smtp",
+                        "mailfrom",
+                        "example.net",
+                    )],
+                },
+            ),
+            (
+                b"Authentication-Results: example.com;\r\n\
+                  \x20         auth=pass (cram-md5) smtp.auth=sender@example.net;\r\n\
+                  \x20         spf=pass smtp.mailfrom=example.net\r\n\r\n",
+                AuthenticationResults {
+                    authserv_id: "example.com".into(),
+                    version: None,
+                    results: vec![
+                        MethodResult::new(Method::Auth, AuthResult::Pass).property(
+                            "smtp",
+                            "auth",
+                            "sender@example.net",
+                        ),
+                        MethodResult::new(Method::Spf, AuthResult::Pass).property(
+                            "smtp",
+                            "mailfrom",
+                            "example.net",
+                        ),
+                    ],
+                },
+            ),
+            (
+                b"Authentication-Results: example.com;\r\n\
+                  \x20   dkim=pass (good signature) header.d=mail-router.example.net;\r\n\
+                  \x20   dkim=fail reason=\"bad signature\"\r\n\
+                  \x20     header.i=@newyork.example.com\r\n\r\n",
+                AuthenticationResults {
+                    authserv_id: "example.com".into(),
+                    version: None,
+                    results: vec![
+                        MethodResult::new(Method::Dkim, AuthResult::Pass).property(
+                            "header",
+                            "d",
+                            "mail-router.example.net",
+                        ),
+                        MethodResult::new(Method::Dkim, AuthResult::Fail)
+                            .reason("bad signature")
+                            .property("header", "i", "@newyork.example.com"),
+                    ],
+                },
+            ),
+            (
+                b"Authentication-Results: \"mx example\" (comment) ; x-method / 2 = SomeResult\r\n\r\n",
+                AuthenticationResults {
+                    authserv_id: "mx example".into(),
+                    version: None,
+                    results: vec![MethodResult {
+                        method: Method::Other("x-method".into()),
+                        method_version: Some(2),
+                        result: AuthResult::Other("SomeResult".into()),
+                        reason: None,
+                        properties: vec![],
+                    }],
+                },
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, got) = authres_header_field(test).unwrap();
+            assert_eq!(rem, b"\r\n");
+            assert_eq!(got, *expected);
+        }
+
+        assert!(authres_header_field(b"Authentication-Results: example.com\r\n\r\n").is_err());
+        assert!(authres_header_field(b"Authentication-Results: example.com; spf\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_pvalue() {
+        let tests: &[(&[u8], &str)] = &[
+            (b"example.net;", "example.net"),
+            (b"@example.net;", "@example.net"),
+            (b"user@example.net;", "user@example.net"),
+            (b" (c) \"quoted value\" (c);", "quoted value"),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, got) = pvalue(test).unwrap();
+            assert_eq!(rem, b";");
+            assert_eq!(got, *expected);
+        }
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let results = AuthenticationResults::builder("mx.example.com")
+            .result(
+                MethodResult::new(Method::Spf, AuthResult::SoftFail)
+                    .reason("not permitted")
+                    .property("smtp", "mailfrom", "sender@example.net"),
+            )
+            .result(MethodResult::new(Method::Dmarc, AuthResult::Pass).property(
+                "header",
+                "from",
+                "example.net",
+            ))
+            .build()
+            .unwrap();
+
+        let mut out = Vec::new();
+        results.serialize(&mut out).unwrap();
+        out.extend_from_slice(b"\r\n");
+
+        let (rem, got) = authres_header_field(&out).unwrap();
+        assert_eq!(rem, b"\r\n");
+        assert_eq!(got, results);
+    }
+
+    #[test]
+    fn test_authentication_results() {
+        let header = b"\
+Authentication-Results: mx.example.com; spf=pass smtp.mailfrom=example.net\r\n\
+Received: from a.example.net by mx.example.com; Thu, 21 May 1998 05:33:29 -0700\r\n\
+Authentication-Results: evil.example.org; malformed\r\n\
+authentication-results: relay.example.org;\r\n\
+\tdkim=pass header.d=example.net\r\n\
+\r\n\
+Authentication-Results: body.example.org; none\r\n";
+
+        let got = authentication_results(header);
+        assert_eq!(got.len(), 2);
+        assert!(got[0].is_from("MX.example.com"));
+        assert!(got[1].is_from("relay.example.org"));
+        assert_eq!(
+            got[1].results[0].properties,
+            vec![Property {
+                ptype: "header".into(),
+                property: "d".into(),
+                value: "example.net".into(),
+            }]
+        );
+
+        let spf: Vec<_> = got[0].results_of(&Method::Spf).collect();
+        assert_eq!(spf.len(), 1);
+        assert_eq!(
+            spf[0].property_value("SMTP", "MailFrom"),
+            Some("example.net")
+        );
+    }
+}
@@ -1,16 +1,23 @@
+use std::borrow::Cow;
+
 use abnf_core::streaming::{is_ALPHA, is_DIGIT, CRLF, SP};
 use nom::{
     branch::alt,
-    bytes::streaming::{tag, tag_no_case, take_while, take_while1, take_while_m_n},
-    combinator::{map, map_res, opt, recognize, value},
+    bytes::streaming::{tag, tag_no_case, take, take_while, take_while1, take_while_m_n},
+    combinator::{cut, map, map_opt, map_res, opt, recognize, value, verify},
+    error::{Error, ErrorKind},
     multi::separated_list1,
-    sequence::{delimited, preceded, tuple},
-    IResult,
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult, Needed,
 };
 
 use crate::{
-    parse::{address::address_literal, atom, base64, domain, quoted_string, string},
-    Command, DomainOrAddress, Parameter,
+    parse::{
+        address::address_literal, atom, atom_utf8, base64, domain, domain_utf8, quoted_string,
+        quoted_string_utf8, string, string_utf8,
+    },
+    xtext, AtomOrQuoted, BodyType, Command, Domain, DomainOrAddress, ForwardPath, Mailbox, Notify,
+    NotifyConditions, NotifyOn, Orcpt, Parameter, Path, Ret, ReversePath,
 };
 
 pub fn command(input: &[u8]) -> IResult<&[u8], Command> {
@@ -19,6 +26,7 @@ pub fn command(input: &[u8]) -> IResult<&[u8], Command> {
         starttls,   // Extensions
         auth_login, // https://interoperability.blob.core.windows.net/files/MS-XLOGIN/[MS-XLOGIN].pdf
         auth_plain, // RFC 4616
+        bdat,       // RFC 3030
     ))(input)
 }
 
@@ -28,10 +36,8 @@ pub fn helo(input: &[u8]) -> IResult<&[u8], Command> {
         tag_no_case(b"HELO"),
         SP,
         alt((
-            map(domain, |domain| DomainOrAddress::Domain(domain.into())),
-            map(address_literal, |address| {
-                DomainOrAddress::Address(address.into())
-            }),
+            map(domain, DomainOrAddress::Domain),
+            map(address_literal, DomainOrAddress::Address),
         )),
         CRLF,
     ));
@@ -47,10 +53,8 @@ pub fn ehlo(input: &[u8]) -> IResult<&[u8], Command> {
         tag_no_case(b"EHLO"),
         SP,
         alt((
-            map(domain, |domain| DomainOrAddress::Domain(domain.into())),
-            map(address_literal, |address| {
-                DomainOrAddress::Address(address.into())
-            }),
+            map(domain, DomainOrAddress::Domain),
+            map(address_literal, DomainOrAddress::Address),
         )),
         CRLF,
     ));
@@ -62,44 +66,84 @@ pub fn ehlo(input: &[u8]) -> IResult<&[u8], Command> {
 
 /// mail = "MAIL FROM:" Reverse-path [SP Mail-parameters] CRLF
 pub fn mail(input: &[u8]) -> IResult<&[u8], Command> {
+    mail_with(input, false)
+}
+
+fn mail_with(input: &[u8], utf8: bool) -> IResult<&[u8], Command> {
     let mut parser = tuple((
         tag_no_case(b"MAIL FROM:"),
         opt(SP), // Out-of-Spec, but Outlook does it ...
-        reverse_path,
-        opt(preceded(SP, mail_parameters)),
+        |input| reverse_path_with(input, utf8),
+        opt(preceded(SP, |input| mail_parameters_with(input, utf8))),
         CRLF,
     ));
 
-    let (remaining, (_, _, data, maybe_params, _)) = parser(input)?;
+    let (remaining, (_, _, reverse_path, maybe_params, _)) = parser(input)?;
 
     Ok((
         remaining,
         Command::Mail {
-            reverse_path: data.into(),
+            reverse_path,
             parameters: maybe_params.unwrap_or_default(),
         },
     ))
 }
 
 /// Mail-parameters = esmtp-param *(SP esmtp-param)
+///
+/// Note: The "RCPT" parameters "NOTIFY" and "ORCPT" are rejected, and so are repeated keywords.
 pub fn mail_parameters(input: &[u8]) -> IResult<&[u8], Vec<Parameter>> {
-    separated_list1(SP, esmtp_param)(input)
+    mail_parameters_with(input, false)
+}
+
+fn mail_parameters_with(input: &[u8], utf8: bool) -> IResult<&[u8], Vec<Parameter>> {
+    let is_mail_parameter =
+        |parameter: &Parameter| !matches!(parameter, Parameter::Notify(_) | Parameter::Orcpt(_));
+
+    verify(
+        separated_list1(SP, |input| esmtp_param_with(input, utf8)),
+        move |parameters: &Vec<Parameter>| {
+            parameters.iter().all(is_mail_parameter) && !has_repeated_keyword(parameters)
+        },
+    )(input)
 }
 
 /// esmtp-param = esmtp-keyword ["=" esmtp-value]
+///
+/// The values of known parameters are validated, i.e., a known parameter with a malformed value
+/// results in a `nom::Err::Failure`. Unknown parameters are returned as [`Parameter::Other`].
 pub fn esmtp_param(input: &[u8]) -> IResult<&[u8], Parameter> {
-    alt((
-        map_res(tuple((tag_no_case(b"SIZE="), esmtp_value)), |(_, value)| {
-            value.parse().map(Parameter::Size)
-        }),
-        map(
-            tuple((esmtp_keyword, opt(preceded(tag(b"="), esmtp_value)))),
-            |(keyword, value)| Parameter::Other {
+    esmtp_param_with(input, false)
+}
+
+fn esmtp_param_with(input: &[u8], utf8: bool) -> IResult<&[u8], Parameter> {
+    let esmtp_value = |input| esmtp_value_with(input, utf8);
+
+    let (rest, keyword) = esmtp_keyword(input)?;
+
+    let (remaining, parameter) = match keyword.to_ascii_uppercase().as_str() {
+        "SIZE" => cut(preceded(
+            tag(b"="),
+            map_res(esmtp_value, |value| value.parse().map(Parameter::Size)),
+        ))(rest)?,
+        "BODY" => cut(preceded(tag(b"="), map(body_value, Parameter::Body)))(rest)?,
+        "RET" => cut(preceded(tag(b"="), map(ret_value, Parameter::Ret)))(rest)?,
+        "ENVID" => cut(preceded(tag(b"="), map(envid_value, Parameter::EnvId)))(rest)?,
+        "NOTIFY" => cut(preceded(tag(b"="), map(notify_value, Parameter::Notify)))(rest)?,
+        "ORCPT" => cut(preceded(
+            tag(b"="),
+            map(|input| orcpt_value_with(input, utf8), Parameter::Orcpt),
+        ))(rest)?,
+        _ => map(opt(preceded(tag(b"="), esmtp_value)), |value| match value {
+            None if keyword.eq_ignore_ascii_case("SMTPUTF8") => Parameter::SmtpUtf8,
+            _ => Parameter::Other {
                 keyword: keyword.to_owned(),
                 value: value.map(String::from),
             },
-        ),
-    ))(input)
+        })(rest)?,
+    };
+
+    Ok((remaining, parameter))
 }
 
 /// esmtp-keyword = (ALPHA / DIGIT) *(ALPHA / DIGIT / "-")
@@ -120,9 +164,12 @@ pub fn esmtp_keyword(input: &[u8]) -> IResult<&[u8], &str> {
 ///
 /// esmtp-value = 1*(%d33-60 / %d62-126)
 pub fn esmtp_value(input: &[u8]) -> IResult<&[u8], &str> {
-    fn is_value_character(byte: u8) -> bool {
-        matches!(byte, 33..=60 | 62..=126)
-    }
+    esmtp_value_with(input, false)
+}
+
+fn esmtp_value_with(input: &[u8], utf8: bool) -> IResult<&[u8], &str> {
+    let is_value_character =
+        |byte: u8| matches!(byte, 33..=60 | 62..=126) || (utf8 && !byte.is_ascii());
 
     map_res(take_while1(is_value_character), std::str::from_utf8)(input)
 }
@@ -133,35 +180,75 @@ pub fn esmtp_value(input: &[u8]) -> IResult<&[u8], &str> {
 /// local-parts, the "Postmaster" string shown above is
 /// treated as case-insensitive.
 pub fn rcpt(input: &[u8]) -> IResult<&[u8], Command> {
+    rcpt_with(input, false)
+}
+
+fn rcpt_with(input: &[u8], utf8: bool) -> IResult<&[u8], Command> {
     let mut parser = tuple((
         tag_no_case(b"RCPT TO:"),
         opt(SP), // Out-of-Spec, but Outlook does it ...
         alt((
-            map_res(
-                recognize(tuple((tag_no_case("<Postmaster@"), domain, tag(">")))),
-                std::str::from_utf8,
+            map(
+                delimited(
+                    tag_no_case("<Postmaster@"),
+                    |input| domain_with(input, utf8),
+                    tag(">"),
+                ),
+                ForwardPath::PostmasterAt,
             ),
-            map_res(tag_no_case("<Postmaster>"), std::str::from_utf8),
-            forward_path,
+            value(ForwardPath::Postmaster, tag_no_case("<Postmaster>")),
+            map(|input| forward_path_with(input, utf8), ForwardPath::Path),
         )),
-        opt(preceded(SP, rcpt_parameters)),
+        opt(preceded(SP, |input| rcpt_parameters_with(input, utf8))),
         CRLF,
     ));
 
-    let (remaining, (_, _, data, maybe_params, _)) = parser(input)?;
+    let (remaining, (_, _, forward_path, maybe_params, _)) = parser(input)?;
 
     Ok((
         remaining,
         Command::Rcpt {
-            forward_path: data.into(),
+            forward_path,
             parameters: maybe_params.unwrap_or_default(),
         },
     ))
 }
 
 /// Rcpt-parameters = esmtp-param *(SP esmtp-param)
+///
+/// Note: The "MAIL" parameters "SIZE", "BODY", "SMTPUTF8", "RET", and "ENVID" are rejected, and
+/// so are repeated keywords.
 pub fn rcpt_parameters(input: &[u8]) -> IResult<&[u8], Vec<Parameter>> {
-    separated_list1(SP, esmtp_param)(input)
+    rcpt_parameters_with(input, false)
+}
+
+fn rcpt_parameters_with(input: &[u8], utf8: bool) -> IResult<&[u8], Vec<Parameter>> {
+    let is_rcpt_parameter = |parameter: &Parameter| {
+        !matches!(
+            parameter,
+            Parameter::Size(_)
+                | Parameter::Body(_)
+                | Parameter::SmtpUtf8
+                | Parameter::Ret(_)
+                | Parameter::EnvId(_)
+        )
+    };
+
+    verify(
+        separated_list1(SP, |input| esmtp_param_with(input, utf8)),
+        move |parameters: &Vec<Parameter>| {
+            parameters.iter().all(is_rcpt_parameter) && !has_repeated_keyword(parameters)
+        },
+    )(input)
+}
+
+/// A keyword must not appear more than once in a command (RFC 1869).
+fn has_repeated_keyword(parameters: &[Parameter]) -> bool {
+    parameters.iter().enumerate().any(|(index, parameter)| {
+        parameters[..index]
+            .iter()
+            .any(|other| other.keyword().eq_ignore_ascii_case(parameter.keyword()))
+    })
 }
 
 /// data = "DATA" CRLF
@@ -176,7 +263,16 @@ pub fn rset(input: &[u8]) -> IResult<&[u8], Command> {
 
 /// vrfy = "VRFY" SP String CRLF
 pub fn vrfy(input: &[u8]) -> IResult<&[u8], Command> {
-    let mut parser = tuple((tag_no_case(b"VRFY"), SP, string, CRLF));
+    vrfy_with(input, false)
+}
+
+fn vrfy_with(input: &[u8], utf8: bool) -> IResult<&[u8], Command> {
+    let mut parser = tuple((
+        tag_no_case(b"VRFY"),
+        SP,
+        |input| string_with(input, utf8),
+        CRLF,
+    ));
 
     let (remaining, (_, _, data, _)) = parser(input)?;
 
@@ -190,7 +286,16 @@ pub fn vrfy(input: &[u8]) -> IResult<&[u8], Command> {
 
 /// expn = "EXPN" SP String CRLF
 pub fn expn(input: &[u8]) -> IResult<&[u8], Command> {
-    let mut parser = tuple((tag_no_case(b"EXPN"), SP, string, CRLF));
+    expn_with(input, false)
+}
+
+fn expn_with(input: &[u8], utf8: bool) -> IResult<&[u8], Command> {
+    let mut parser = tuple((
+        tag_no_case(b"EXPN"),
+        SP,
+        |input| string_with(input, utf8),
+        CRLF,
+    ));
 
     let (remaining, (_, _, data, _)) = parser(input)?;
 
@@ -199,7 +304,15 @@ pub fn expn(input: &[u8]) -> IResult<&[u8], Command> {
 
 /// help = "HELP" [ SP String ] CRLF
 pub fn help(input: &[u8]) -> IResult<&[u8], Command> {
-    let mut parser = tuple((tag_no_case(b"HELP"), opt(preceded(SP, string)), CRLF));
+    help_with(input, false)
+}
+
+fn help_with(input: &[u8], utf8: bool) -> IResult<&[u8], Command> {
+    let mut parser = tuple((
+        tag_no_case(b"HELP"),
+        opt(preceded(SP, |input| string_with(input, utf8))),
+        CRLF,
+    ));
 
     let (remaining, (_, maybe_data, _)) = parser(input)?;
 
@@ -213,7 +326,15 @@ pub fn help(input: &[u8]) -> IResult<&[u8], Command> {
 
 /// noop = "NOOP" [ SP String ] CRLF
 pub fn noop(input: &[u8]) -> IResult<&[u8], Command> {
-    let mut parser = tuple((tag_no_case(b"NOOP"), opt(preceded(SP, string)), CRLF));
+    noop_with(input, false)
+}
+
+fn noop_with(input: &[u8], utf8: bool) -> IResult<&[u8], Command> {
+    let mut parser = tuple((
+        tag_no_case(b"NOOP"),
+        opt(preceded(SP, |input| string_with(input, utf8))),
+        CRLF,
+    ));
 
     let (remaining, (_, maybe_data, _)) = parser(input)?;
 
@@ -279,90 +400,438 @@ pub fn auth_plain(input: &[u8]) -> IResult<&[u8], Command> {
     ))
 }
 
+/// bdat-cmd = "BDAT" SP chunk-size [ SP end-marker ] CR LF
+///
+/// The command is followed by exactly chunk-size octets of message data, see [`chunk_data`],
+/// [`chunk_data_partial`], and [`bdat_chunk`].
+pub fn bdat(input: &[u8]) -> IResult<&[u8], Command> {
+    map(bdat_cmd, |(size, last)| Command::Bdat { size, last })(input)
+}
+
+/// bdat-cmd followed by its chunk of message data
+///
+/// Returns the command and the chunk. Pipelined "BDAT" commands are parsed by calling this parser
+/// repeatedly with the remaining input.
+///
+/// Note: The whole chunk must be buffered. Thus, a chunk-size larger than `max_size` results in a
+/// `nom::Err::Failure` before any data is awaited. Use [`bdat`] and [`chunk_data_partial`] to
+/// process large chunks incrementally.
+pub fn bdat_chunk(max_size: u64) -> impl Fn(&[u8]) -> IResult<&[u8], (Command, &[u8])> {
+    move |input| {
+        let (rest, (size, last)) = bdat_cmd(input)?;
+
+        if size > max_size {
+            return Err(nom::Err::Failure(Error::new(input, ErrorKind::TooLarge)));
+        }
+
+        let (remaining, chunk) = chunk_data(size)(rest)?;
+
+        Ok((remaining, (Command::Bdat { size, last }, chunk)))
+    }
+}
+
+/// Take exactly `size` octets of message data, e.g., the chunk following a "BDAT" command.
+///
+/// The data is not interpreted, i.e., it may contain CRLF, "." or anything that looks like a
+/// command. Returns `nom::Err::Incomplete` until all octets are available.
+pub fn chunk_data(size: u64) -> impl Fn(&[u8]) -> IResult<&[u8], &[u8]> {
+    move |input| match usize::try_from(size) {
+        Ok(size) => take(size)(input),
+        Err(_) => Err(nom::Err::Failure(Error::new(input, ErrorKind::TooLarge))),
+    }
+}
+
+/// Take up to `remaining` octets of message data, i.e., `min(input.len(), remaining)` octets.
+///
+/// Use this parser to consume a chunk incrementally without buffering it: Subtract the length of
+/// each returned part from `remaining` until it is zero. Returns `nom::Err::Incomplete` when the
+/// input is empty but more octets are remaining.
+pub fn chunk_data_partial(remaining: u64) -> impl Fn(&[u8]) -> IResult<&[u8], &[u8]> {
+    move |input| {
+        if input.is_empty() && remaining > 0 {
+            return Err(nom::Err::Incomplete(Needed::new(1)));
+        }
+
+        let size = usize::try_from(remaining).map_or(input.len(), |size| size.min(input.len()));
+
+        Ok((&input[size..], &input[..size]))
+    }
+}
+
+/// bdat-cmd   = "BDAT" SP chunk-size [ SP end-marker ] CR LF
+/// chunk-size = 1*DIGIT
+/// end-marker = "LAST"
+fn bdat_cmd(input: &[u8]) -> IResult<&[u8], (u64, bool)> {
+    let mut parser = tuple((
+        tag_no_case(b"BDAT"),
+        SP,
+        map_res(
+            map_res(take_while1(is_DIGIT), std::str::from_utf8),
+            str::parse::<u64>,
+        ),
+        opt(preceded(SP, tag_no_case(b"LAST"))),
+        CRLF,
+    ));
+
+    let (remaining, (_, _, size, last, _)) = parser(input)?;
+
+    Ok((remaining, (size, last.is_some())))
+}
+
 // ----- 4.1.2.  Command Argument Syntax (RFC 5321) -----
 
 /// Reverse-path = Path / "<>"
-pub fn reverse_path(input: &[u8]) -> IResult<&[u8], &str> {
-    alt((path, value("", tag("<>"))))(input)
+pub fn reverse_path(input: &[u8]) -> IResult<&[u8], ReversePath> {
+    reverse_path_with(input, false)
+}
+
+fn reverse_path_with(input: &[u8], utf8: bool) -> IResult<&[u8], ReversePath> {
+    alt((
+        map(|input| path_with(input, utf8), ReversePath::Path),
+        value(ReversePath::Null, tag("<>")),
+    ))(input)
 }
 
 /// Forward-path = Path
-pub fn forward_path(input: &[u8]) -> IResult<&[u8], &str> {
-    path(input)
+pub fn forward_path(input: &[u8]) -> IResult<&[u8], Path> {
+    forward_path_with(input, false)
+}
+
+fn forward_path_with(input: &[u8], utf8: bool) -> IResult<&[u8], Path> {
+    path_with(input, utf8)
 }
 
-// Path = "<" [ A-d-l ":" ] Mailbox ">"
-pub fn path(input: &[u8]) -> IResult<&[u8], &str> {
-    delimited(
+/// Path = "<" [ A-d-l ":" ] Mailbox ">"
+pub fn path(input: &[u8]) -> IResult<&[u8], Path> {
+    path_with(input, false)
+}
+
+fn path_with(input: &[u8], utf8: bool) -> IResult<&[u8], Path> {
+    let mut parser = delimited(
         tag(b"<"),
-        map_res(
-            recognize(tuple((opt(tuple((a_d_l, tag(b":")))), mailbox))),
-            std::str::from_utf8,
-        ),
+        tuple((
+            opt(terminated(|input| a_d_l_with(input, utf8), tag(b":"))),
+            |input| mailbox_with(input, utf8),
+        )),
         tag(b">"),
-    )(input)
+    );
+
+    let (remaining, (source_route, mailbox)) = parser(input)?;
+
+    Ok((
+        remaining,
+        Path {
+            source_route: source_route.unwrap_or_default(),
+            mailbox,
+        },
+    ))
 }
 
 /// A-d-l = At-domain *( "," At-domain )
 ///          ; Note that this form, the so-called "source
 ///          ; route", MUST BE accepted, SHOULD NOT be
 ///          ; generated, and SHOULD be ignored.
-pub fn a_d_l(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = separated_list1(tag(b","), at_domain);
-
-    let (remaining, parsed) = recognize(parser)(input)?;
+pub fn a_d_l(input: &[u8]) -> IResult<&[u8], Vec<Domain>> {
+    a_d_l_with(input, false)
+}
 
-    Ok((remaining, parsed))
+fn a_d_l_with(input: &[u8], utf8: bool) -> IResult<&[u8], Vec<Domain>> {
+    separated_list1(tag(b","), |input| at_domain_with(input, utf8))(input)
 }
 
 /// At-domain = "@" Domain
-pub fn at_domain(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((tag(b"@"), domain));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
+pub fn at_domain(input: &[u8]) -> IResult<&[u8], Domain> {
+    at_domain_with(input, false)
+}
 
-    Ok((remaining, parsed))
+fn at_domain_with(input: &[u8], utf8: bool) -> IResult<&[u8], Domain> {
+    preceded(tag(b"@"), |input| domain_with(input, utf8))(input)
 }
 
 /// Mailbox = Local-part "@" ( Domain / address-literal )
-pub fn mailbox(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((local_part, tag(b"@"), alt((domain, address_literal))));
+pub fn mailbox(input: &[u8]) -> IResult<&[u8], Mailbox> {
+    mailbox_with(input, false)
+}
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+fn mailbox_with(input: &[u8], utf8: bool) -> IResult<&[u8], Mailbox> {
+    let parser = tuple((
+        |input| local_part_with(input, utf8),
+        tag(b"@"),
+        alt((
+            map(|input| domain_with(input, utf8), DomainOrAddress::Domain),
+            map(address_literal, DomainOrAddress::Address),
+        )),
+    ));
 
-    Ok((remaining, parsed))
+    let (remaining, mailbox) = map_res(parser, |(local_part, _, domain_or_literal)| {
+        Mailbox::new(local_part, domain_or_literal)
+    })(input)?;
+
+    Ok((remaining, mailbox))
 }
 
 /// Local-part = Dot-string / Quoted-string
 ///               ; MAY be case-sensitive
-pub fn local_part(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    alt((recognize(dot_string), recognize(quoted_string)))(input)
+///
+/// Returns the content of a Quoted-string.
+pub fn local_part(input: &[u8]) -> IResult<&[u8], String> {
+    local_part_with(input, false)
+}
+
+fn local_part_with(input: &[u8], utf8: bool) -> IResult<&[u8], String> {
+    let quoted_string = |input| match utf8 {
+        true => quoted_string_utf8(input),
+        false => quoted_string(input),
+    };
+
+    alt((
+        map(|input| dot_string_with(input, utf8), str::to_owned),
+        map(quoted_string, Cow::into_owned),
+    ))(input)
 }
 
 /// Dot-string = Atom *("."  Atom)
 pub fn dot_string(input: &[u8]) -> IResult<&[u8], &str> {
+    dot_string_with(input, false)
+}
+
+fn dot_string_with(input: &[u8], utf8: bool) -> IResult<&[u8], &str> {
+    let atom = |input| match utf8 {
+        true => atom_utf8(input),
+        false => atom(input),
+    };
+
     map_res(
         recognize(separated_list1(tag(b"."), atom)),
         std::str::from_utf8,
     )(input)
 }
 
+fn domain_with(input: &[u8], utf8: bool) -> IResult<&[u8], Domain> {
+    match utf8 {
+        true => domain_utf8(input),
+        false => domain(input),
+    }
+}
+
+fn string_with(input: &[u8], utf8: bool) -> IResult<&[u8], AtomOrQuoted> {
+    match utf8 {
+        true => string_utf8(input),
+        false => string(input),
+    }
+}
+
+// ----- 3.  Extended Mailbox Address Syntax (RFC 6531) -----
+
+/// Like [`command`], but accepts UTF-8 in MAIL, RCPT, VRFY, EXPN, HELP, and NOOP.
+///
+/// Use this parser when SMTPUTF8 was negotiated.
+pub fn command_utf8(input: &[u8]) -> IResult<&[u8], Command> {
+    alt((
+        helo, ehlo, mail_utf8, rcpt_utf8, data, rset, vrfy_utf8, expn_utf8, help_utf8, noop_utf8,
+        quit, starttls, auth_login, auth_plain, bdat,
+    ))(input)
+}
+
+/// mail = "MAIL FROM:" Reverse-path [SP Mail-parameters] CRLF
+///
+/// esmtp-value =/ UTF8-non-ascii
+pub fn mail_utf8(input: &[u8]) -> IResult<&[u8], Command> {
+    mail_with(input, true)
+}
+
+/// rcpt = "RCPT TO:" ( "<Postmaster@" Domain ">" / "<Postmaster>" / Forward-path ) [SP Rcpt-parameters] CRLF
+///
+/// esmtp-value =/ UTF8-non-ascii
+pub fn rcpt_utf8(input: &[u8]) -> IResult<&[u8], Command> {
+    rcpt_with(input, true)
+}
+
+/// vrfy = "VRFY" SP String CRLF
+pub fn vrfy_utf8(input: &[u8]) -> IResult<&[u8], Command> {
+    vrfy_with(input, true)
+}
+
+/// expn = "EXPN" SP String CRLF
+pub fn expn_utf8(input: &[u8]) -> IResult<&[u8], Command> {
+    expn_with(input, true)
+}
+
+/// help = "HELP" [ SP String ] CRLF
+pub fn help_utf8(input: &[u8]) -> IResult<&[u8], Command> {
+    help_with(input, true)
+}
+
+/// noop = "NOOP" [ SP String ] CRLF
+pub fn noop_utf8(input: &[u8]) -> IResult<&[u8], Command> {
+    noop_with(input, true)
+}
+
+/// Reverse-path = Path / "<>"
+pub fn reverse_path_utf8(input: &[u8]) -> IResult<&[u8], ReversePath> {
+    reverse_path_with(input, true)
+}
+
+/// Forward-path = Path
+pub fn forward_path_utf8(input: &[u8]) -> IResult<&[u8], Path> {
+    forward_path_with(input, true)
+}
+
+/// Path = "<" [ A-d-l ":" ] Mailbox ">"
+pub fn path_utf8(input: &[u8]) -> IResult<&[u8], Path> {
+    path_with(input, true)
+}
+
+/// Mailbox = Local-part "@" ( Domain / address-literal )
+///
+/// sub-domain =/ U-label
+pub fn mailbox_utf8(input: &[u8]) -> IResult<&[u8], Mailbox> {
+    mailbox_with(input, true)
+}
+
+/// Local-part = Dot-string / Quoted-string
+///
+/// atext =/ UTF8-non-ascii
+/// qtextSMTP =/ UTF8-non-ascii
+pub fn local_part_utf8(input: &[u8]) -> IResult<&[u8], String> {
+    local_part_with(input, true)
+}
+
+// ----- BODY (RFC 6152, RFC 3030) -----
+
+/// body-value = "7BIT" / "8BITMIME" / "BINARYMIME"
+pub fn body_value(input: &[u8]) -> IResult<&[u8], BodyType> {
+    alt((
+        value(BodyType::SevenBit, tag_no_case(b"7BIT")),
+        value(BodyType::EightBitMime, tag_no_case(b"8BITMIME")),
+        value(BodyType::BinaryMime, tag_no_case(b"BINARYMIME")),
+    ))(input)
+}
+
+// ----- 4.  Additional Parameters for SMTP Commands (RFC 3461) -----
+
+/// ret-value = "FULL" / "HDRS"
+pub fn ret_value(input: &[u8]) -> IResult<&[u8], Ret> {
+    alt((
+        value(Ret::Full, tag_no_case(b"FULL")),
+        value(Ret::Hdrs, tag_no_case(b"HDRS")),
+    ))(input)
+}
+
+/// envid-value = xtext
+///
+/// The decoded value must consist of printable ASCII characters and must not be empty.
+pub fn envid_value(input: &[u8]) -> IResult<&[u8], String> {
+    verify(xtext, |envid: &str| {
+        !envid.is_empty() && envid.bytes().all(|byte| matches!(byte, 32..=126))
+    })(input)
+}
+
+/// notify-esmtp-value  = "NEVER" / 1#notify-list-element
+/// notify-list-element = "SUCCESS" / "FAILURE" / "DELAY"
+///
+/// Note: "NEVER" must not be combined with other values and values must not be repeated.
+pub fn notify_value(input: &[u8]) -> IResult<&[u8], Notify> {
+    alt((
+        value(Notify::Never, tag_no_case(b"NEVER")),
+        map(
+            map_res(
+                separated_list1(
+                    tag(b","),
+                    alt((
+                        value(NotifyOn::Success, tag_no_case(b"SUCCESS")),
+                        value(NotifyOn::Failure, tag_no_case(b"FAILURE")),
+                        value(NotifyOn::Delay, tag_no_case(b"DELAY")),
+                    )),
+                ),
+                NotifyConditions::new,
+            ),
+            Notify::On,
+        ),
+    ))(input)
+}
+
+/// orcpt-value = addr-type ";" xtext
+///
+/// addr-type = atom
+///
+/// The "utf-8" address type uses utf-8-addr-xtext or utf-8-addr-unitext instead of xtext
+/// (RFC 6533). The address is decoded.
+///
+/// Note: utf-8-addr-unitext is only accepted when SMTPUTF8 was negotiated, see [`mail_utf8`] and
+/// [`rcpt_utf8`].
+pub fn orcpt_value(input: &[u8]) -> IResult<&[u8], Orcpt> {
+    orcpt_value_with(input, false)
+}
+
+fn orcpt_value_with(input: &[u8], utf8: bool) -> IResult<&[u8], Orcpt> {
+    let parser = tuple((
+        atom,
+        tag(b";"),
+        map_res(
+            take_while(|byte: u8| matches!(byte, 33..=60 | 62..=126) || (utf8 && !byte.is_ascii())),
+            std::str::from_utf8,
+        ),
+    ));
+
+    let (remaining, orcpt) = map_opt(parser, |(addr_type, _, address)| {
+        let address = match addr_type.eq_ignore_ascii_case("utf-8") {
+            true => xtext::decode_utf8_addr(address).ok()?,
+            false => xtext::decode(address).ok()?,
+        };
+
+        Orcpt::new(addr_type, address).ok()
+    })(input)?;
+
+    Ok((remaining, orcpt))
+}
+
+/// xtext = *( xchar / hexchar )
+///
+/// xchar = any ASCII CHAR between "!" (33) and "~" (126) inclusive, except for "+" and "=".
+///
+/// hexchar = ASCII "+" immediately followed by two upper case hexadecimal digits
+///
+/// Returns the decoded value, see [`xtext::decode`].
+pub fn xtext(input: &[u8]) -> IResult<&[u8], String> {
+    map_res(
+        map_res(
+            take_while(|byte| xtext::is_xchar(byte) || byte == b'+'),
+            std::str::from_utf8,
+        ),
+        xtext::decode,
+    )(input)
+}
+
 // Not used?
-/// Keyword = Ldh-str
+// Keyword = Ldh-str
 //pub fn keyword(input: &[u8]) -> IResult<&[u8], &[u8]> {
 //    ldh_str(input)
 //}
 
 // Not used?
-/// Argument = Atom
+// Argument = Atom
 //pub fn argument(input: &[u8]) -> IResult<&[u8], &[u8]> {
 //    atom(input)
 //}
 
 #[cfg(test)]
 mod test {
-    use super::{ehlo, helo, mail, Parameter};
-    use crate::{Command, DomainOrAddress};
+    use std::net::Ipv4Addr;
+
+    use nom::{
+        error::{Error, ErrorKind},
+        Needed,
+    };
+
+    use super::{
+        bdat, bdat_chunk, chunk_data_partial, command, command_utf8, ehlo, esmtp_param, helo, mail,
+        mail_utf8, rcpt, rcpt_utf8, xtext, Parameter,
+    };
+    use crate::{
+        BodyType, Capability, Command, Domain, DomainOrAddress, ForwardPath, Mailbox, Notify,
+        NotifyConditions, NotifyOn, Orcpt, Path, Ret, ReversePath,
+    };
 
     #[test]
     fn test_ehlo() {
@@ -370,7 +839,9 @@ mod test {
         assert_eq!(
             parsed,
             Command::Ehlo {
-                domain_or_address: DomainOrAddress::Address("123.123.123.123".into()),
+                domain_or_address: DomainOrAddress::Address(
+                    Ipv4Addr::new(123, 123, 123, 123).into()
+                ),
             }
         );
         assert_eq!(rem, b"???");
@@ -382,7 +853,7 @@ mod test {
         assert_eq!(
             parsed,
             Command::Helo {
-                domain_or_address: DomainOrAddress::Domain("example.com".into()),
+                domain_or_address: DomainOrAddress::Domain(Domain::new("example.com").unwrap()),
             }
         );
         assert_eq!(rem, b"???");
@@ -394,10 +865,524 @@ mod test {
         assert_eq!(
             parsed,
             Command::Mail {
-                reverse_path: "userx@y.foo.org".into(),
+                reverse_path: ReversePath::Path(Path {
+                    source_route: vec![],
+                    mailbox: Mailbox::new(
+                        "userx",
+                        DomainOrAddress::Domain(Domain::new("y.foo.org").unwrap())
+                    )
+                    .unwrap(),
+                }),
                 parameters: vec![Parameter::Size(12345)],
             }
         );
         assert_eq!(rem, b"???");
     }
+
+    #[test]
+    fn test_bdat() {
+        let tests: &[(&[u8], Command, &[u8])] = &[
+            (
+                b"BDAT 1000\r\n",
+                Command::Bdat {
+                    size: 1000,
+                    last: false,
+                },
+                b"BDAT 1000\r\n",
+            ),
+            (
+                b"bdat 0 last\r\n",
+                Command::Bdat {
+                    size: 0,
+                    last: true,
+                },
+                b"BDAT 0 LAST\r\n",
+            ),
+        ];
+
+        for (test, expected, serialized) in tests {
+            let (rem, got) = command(test).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(got, *expected);
+
+            let mut out = Vec::new();
+            got.serialize(&mut out).unwrap();
+            assert_eq!(out, *serialized);
+        }
+
+        let tests: &[&[u8]] = &[
+            b"BDAT\r\n",
+            b"BDAT LAST\r\n",
+            b"BDAT -1\r\n",
+            b"BDAT 1 FIRST\r\n",
+            b"BDAT 18446744073709551616\r\n",
+        ];
+
+        for test in tests {
+            assert!(command(test).is_err(), "{:?}", std::str::from_utf8(test));
+        }
+    }
+
+    #[test]
+    fn test_bdat_chunk() {
+        // The chunks contain CRLF, "." and "BDAT", which must not be interpreted.
+        let input = b"BDAT 6\r\nBDAT\r\nBDAT 3 LAST\r\n\r\n.QUIT\r\n";
+
+        let (rem, (got, chunk)) = bdat_chunk(1024)(input).unwrap();
+        assert_eq!(
+            got,
+            Command::Bdat {
+                size: 6,
+                last: false
+            }
+        );
+        assert_eq!(chunk, b"BDAT\r\n");
+
+        let (rem, (got, chunk)) = bdat_chunk(1024)(rem).unwrap();
+        assert_eq!(
+            got,
+            Command::Bdat {
+                size: 3,
+                last: true
+            }
+        );
+        assert_eq!(chunk, b"\r\n.");
+
+        let (rem, got) = command(rem).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(got, Command::Quit);
+
+        assert_eq!(
+            bdat_chunk(1024)(b"BDAT 10\r\nabc"),
+            Err(nom::Err::Incomplete(Needed::new(7)))
+        );
+
+        // Too large chunks are rejected before their data arrives.
+        let input = b"BDAT 1025\r\n";
+        assert_eq!(
+            bdat_chunk(1024)(input),
+            Err(nom::Err::Failure(Error::new(
+                &input[..],
+                ErrorKind::TooLarge
+            )))
+        );
+    }
+
+    #[test]
+    fn test_chunk_data_partial() {
+        let (rem, got) = bdat(b"BDAT 10 LAST\r\nabcd").unwrap();
+        assert_eq!(
+            got,
+            Command::Bdat {
+                size: 10,
+                last: true
+            }
+        );
+
+        let mut remaining = 10;
+        let mut chunk = Vec::new();
+
+        let inputs: [&[u8]; 3] = [rem, b"efg", b"hijQUIT\r\n"];
+
+        for input in inputs {
+            let (rem, part) = chunk_data_partial(remaining)(input).unwrap();
+            remaining -= part.len() as u64;
+            chunk.extend_from_slice(part);
+
+            if remaining == 0 {
+                assert_eq!(rem, b"QUIT\r\n");
+            }
+        }
+
+        assert_eq!(remaining, 0);
+        assert_eq!(chunk, b"abcdefghij");
+
+        assert_eq!(
+            chunk_data_partial(1)(b""),
+            Err(nom::Err::Incomplete(Needed::new(1)))
+        );
+        assert_eq!(chunk_data_partial(0)(b"QUIT"), Ok((&b"QUIT"[..], &b""[..])));
+    }
+
+    #[test]
+    fn test_body_parameter() {
+        let tests: &[(&[u8], BodyType, &[u8])] = &[
+            (b"BODY=7BIT\r\n", BodyType::SevenBit, b"BODY=7BIT"),
+            (
+                b"body=8bitmime\r\n",
+                BodyType::EightBitMime,
+                b"BODY=8BITMIME",
+            ),
+            (
+                b"BODY=BINARYMIME\r\n",
+                BodyType::BinaryMime,
+                b"BODY=BINARYMIME",
+            ),
+        ];
+
+        for (test, expected, serialized) in tests {
+            let (rem, got) = esmtp_param(test).unwrap();
+            assert_eq!(rem, b"\r\n");
+            assert_eq!(got, Parameter::Body(*expected));
+
+            let mut out = Vec::new();
+            got.serialize(&mut out).unwrap();
+            assert_eq!(out, *serialized);
+        }
+
+        assert!(mail(b"MAIL FROM:<> BODY=8BIT\r\n").is_err());
+        assert!(mail(b"MAIL FROM:<> BODY\r\n").is_err());
+
+        let eight_bit = [Capability::EightBitMIME];
+        let binary = [Capability::BinaryMIME, Capability::Chunking];
+        assert!(BodyType::SevenBit.is_supported(&[]));
+        assert!(BodyType::EightBitMime.is_supported(&eight_bit));
+        assert!(!BodyType::EightBitMime.is_supported(&binary));
+        assert!(BodyType::BinaryMime.is_supported(&binary));
+        assert!(!BodyType::BinaryMime.is_supported(&[Capability::BinaryMIME]));
+        assert!(BodyType::BinaryMime.requires_bdat());
+        assert!(!BodyType::EightBitMime.requires_bdat());
+    }
+
+    #[test]
+    fn test_dsn_parameters() {
+        let tests: &[(&[u8], Parameter, &[u8])] = &[
+            (b"RET=FULL", Parameter::Ret(Ret::Full), b"RET=FULL"),
+            (b"ret=hdrs", Parameter::Ret(Ret::Hdrs), b"RET=HDRS"),
+            (
+                b"ENVID=QQ314159+2B1",
+                Parameter::EnvId("QQ314159+1".into()),
+                b"ENVID=QQ314159+2B1",
+            ),
+            (
+                b"NOTIFY=NEVER",
+                Parameter::Notify(Notify::Never),
+                b"NOTIFY=NEVER",
+            ),
+            (
+                b"NOTIFY=success,DELAY",
+                Parameter::Notify(Notify::On(
+                    NotifyConditions::new(vec![NotifyOn::Success, NotifyOn::Delay]).unwrap(),
+                )),
+                b"NOTIFY=SUCCESS,DELAY",
+            ),
+            (
+                b"ORCPT=rfc822;Bob+20Smith+3Dbob@example.com",
+                Parameter::Orcpt(Orcpt::new("rfc822", "Bob Smith=bob@example.com").unwrap()),
+                b"ORCPT=rfc822;Bob+20Smith+3Dbob@example.com",
+            ),
+            (
+                b"ORCPT=UTF-8;\\x{7528}\\x{6237}\\x{20}@\\x{4F8B}\\x{5B50}.\\x{5E7F}\\x{544A}",
+                Parameter::Orcpt(Orcpt::new("UTF-8", "用户 @例子.广告").unwrap()),
+                b"ORCPT=UTF-8;\\x{7528}\\x{6237}\\x{20}@\\x{4F8B}\\x{5B50}.\\x{5E7F}\\x{544A}",
+            ),
+        ];
+
+        for (test, expected, serialized) in tests {
+            let test = [test, &b"\r\n"[..]].concat();
+            let (rem, got) = esmtp_param(&test).unwrap();
+            assert_eq!(rem, b"\r\n");
+            assert_eq!(got, *expected);
+            assert!(got.is_dsn());
+
+            let mut out = Vec::new();
+            got.serialize(&mut out).unwrap();
+            assert_eq!(out, *serialized);
+        }
+
+        let tests: &[&[u8]] = &[
+            b"RET=BODY\r\n",
+            b"RET\r\n",
+            b"ENVID=\r\n",
+            b"ENVID=a+2b\r\n",
+            b"ENVID=a+2\r\n",
+            b"ENVID=+0D+0A\r\n",
+            b"NOTIFY=NEVER,SUCCESS\r\n",
+            b"NOTIFY=SUCCESS,NEVER\r\n",
+            b"NOTIFY=DELAY,DELAY\r\n",
+            b"ORCPT=bob@example.com\r\n",
+            b"ORCPT=rfc822;\xC3\xBC\r\n",
+            b"ORCPT=utf-8;bob+2B1@example.com\r\n",
+            b"ORCPT=utf-8;\\x{41}@example.com\r\n",
+            b"SIZE=big\r\n",
+        ];
+
+        for test in tests {
+            let test = [&b"MAIL FROM:<> "[..], test].concat();
+            assert!(mail(&test).is_err(), "{:?}", std::str::from_utf8(&test));
+        }
+
+        let (_, parsed) =
+            rcpt(b"RCPT TO:<bob@example.com> NOTIFY=FAILURE ORCPT=rfc822;bob@example.com\r\n")
+                .unwrap();
+        assert_eq!(
+            parsed,
+            Command::Rcpt {
+                forward_path: ForwardPath::Path(Path::from(
+                    Mailbox::new("bob", Domain::new("example.com").unwrap().into()).unwrap()
+                )),
+                parameters: vec![
+                    Parameter::Notify(Notify::On(
+                        NotifyConditions::new(vec![NotifyOn::Failure]).unwrap()
+                    )),
+                    Parameter::Orcpt(Orcpt::new("rfc822", "bob@example.com").unwrap()),
+                ],
+            }
+        );
+
+        let tests: &[&[u8]] = &[
+            b"MAIL FROM:<> RET=FULL ENVID=QQ314159 SIZE=1\r\n",
+            b"RCPT TO:<bob@example.com> NOTIFY=NEVER ORCPT=rfc822;bob@example.com\r\n",
+        ];
+
+        for test in tests {
+            assert!(command(test).is_ok(), "{:?}", std::str::from_utf8(test));
+        }
+
+        // utf-8-addr-unitext is only accepted when SMTPUTF8 was negotiated.
+        let test = "RCPT TO:<bob@example.com> ORCPT=utf-8;用户\\x{20}@例子.广告\r\n".as_bytes();
+        assert!(rcpt(test).is_err());
+        let (_, parsed) = rcpt_utf8(test).unwrap();
+        assert_eq!(
+            parsed,
+            Command::Rcpt {
+                forward_path: ForwardPath::Path(Path::from(
+                    Mailbox::new("bob", Domain::new("example.com").unwrap().into()).unwrap()
+                )),
+                parameters: vec![Parameter::Orcpt(
+                    Orcpt::new("utf-8", "用户 @例子.广告").unwrap()
+                )],
+            }
+        );
+
+        // Misplaced or repeated parameters are rejected.
+        let tests: &[&[u8]] = &[
+            b"MAIL FROM:<> NOTIFY=NEVER\r\n",
+            b"MAIL FROM:<> ORCPT=rfc822;bob@example.com\r\n",
+            b"MAIL FROM:<> RET=FULL RET=HDRS\r\n",
+            b"MAIL FROM:<> X-A=1 x-a=2\r\n",
+            b"RCPT TO:<bob@example.com> RET=FULL\r\n",
+            b"RCPT TO:<bob@example.com> ENVID=QQ314159\r\n",
+            b"RCPT TO:<bob@example.com> SIZE=1\r\n",
+            b"RCPT TO:<bob@example.com> NOTIFY=NEVER NOTIFY=DELAY\r\n",
+        ];
+
+        for test in tests {
+            assert!(command(test).is_err(), "{:?}", std::str::from_utf8(test));
+        }
+    }
+
+    #[test]
+    fn test_xtext() {
+        let tests: &[(&[u8], &str)] = &[
+            (b" ", ""),
+            (b"abc ", "abc"),
+            (b"+2B+3D ", "+="),
+            (b"+C3+BC ", "\u{fc}"),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, got) = xtext(test).unwrap();
+            assert_eq!(rem, b" ");
+            assert_eq!(got, *expected);
+        }
+
+        // A hexchar must use upper case hex digits and must decode to valid UTF-8.
+        assert!(xtext(b"a+2b ").is_err());
+        assert!(xtext(b"a+2 ").is_err());
+        assert!(xtext(b"+C3 ").is_err());
+    }
+
+    #[test]
+    fn test_rcpt() {
+        let tests: &[(&[u8], ForwardPath)] = &[
+            (b"RCPT TO:<Postmaster>\r\n", ForwardPath::Postmaster),
+            (
+                b"RCPT TO:<postmaster@example.com>\r\n",
+                ForwardPath::PostmasterAt(Domain::new("example.com").unwrap()),
+            ),
+            (
+                b"RCPT TO:<\"a@b\"@c.org>\r\n",
+                ForwardPath::Path(Path {
+                    source_route: vec![],
+                    mailbox: Mailbox::new(
+                        "a@b",
+                        DomainOrAddress::Domain(Domain::new("c.org").unwrap()),
+                    )
+                    .unwrap(),
+                }),
+            ),
+            (
+                b"RCPT TO:<@hosta.int,@jkl.org:userc@[192.0.2.1]>\r\n",
+                ForwardPath::Path(Path {
+                    source_route: vec![
+                        Domain::new("hosta.int").unwrap(),
+                        Domain::new("jkl.org").unwrap(),
+                    ],
+                    mailbox: Mailbox::new(
+                        "userc",
+                        DomainOrAddress::Address(Ipv4Addr::new(192, 0, 2, 1).into()),
+                    )
+                    .unwrap(),
+                }),
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, parsed) = rcpt(test).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(
+                parsed,
+                Command::Rcpt {
+                    forward_path: expected.clone(),
+                    parameters: vec![],
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_serialize_paths() {
+        let tests: &[&[u8]] = &[
+            b"MAIL FROM:<>\r\n",
+            b"MAIL FROM:<userx@y.foo.org> SIZE=12345\r\n",
+            b"MAIL FROM:<@a.org,@b.org:\"john doe\"@example.com>\r\n",
+            b"RCPT TO:<Postmaster>\r\n",
+            b"RCPT TO:<Postmaster@example.com>\r\n",
+            b"RCPT TO:<\"a@b\"@c.org>\r\n",
+            b"RCPT TO:<a.b@[IPv6:2001:db8::1]>\r\n",
+        ];
+
+        for test in tests {
+            let (rem, parsed) = command(test).unwrap();
+            assert!(rem.is_empty());
+
+            let mut out = Vec::new();
+            parsed.serialize(&mut out).unwrap();
+            assert_eq!(out, *test);
+        }
+
+        // Quoting and escaping are only used when required.
+        let tests: &[(&[u8], &[u8])] = &[
+            (
+                b"MAIL FROM:<\"john.doe\"@example.com>\r\n",
+                b"MAIL FROM:<john.doe@example.com>\r\n",
+            ),
+            (
+                b"MAIL FROM:<\"john\\ smith\"@example.com>\r\n",
+                b"MAIL FROM:<\"john smith\"@example.com>\r\n",
+            ),
+            (
+                b"RCPT TO:<\"\\j\\o\\h\\n\"@example.com>\r\n",
+                b"RCPT TO:<john@example.com>\r\n",
+            ),
+            (b"VRFY \"a\\b\\\"\"\r\n", b"VRFY \"ab\\\"\"\r\n"),
+            (b"NOOP \"\\ \"\r\n", b"NOOP \" \"\r\n"),
+        ];
+
+        for (test, expected) in tests {
+            let (_, parsed) = command(test).unwrap();
+            let mut out = Vec::new();
+            parsed.serialize(&mut out).unwrap();
+            assert_eq!(out, *expected);
+        }
+    }
+
+    #[test]
+    fn test_local_part_roundtrip() {
+        // Every printable ASCII character must survive serialization and parsing.
+        for byte in 32..=126u8 {
+            let local_part = format!("a{}b", byte as char);
+            let mailbox =
+                Mailbox::new(local_part, Domain::new("example.com").unwrap().into()).unwrap();
+            let command = Command::Rcpt {
+                forward_path: ForwardPath::Path(Path::from(mailbox)),
+                parameters: vec![],
+            };
+
+            let mut out = Vec::new();
+            command.serialize(&mut out).unwrap();
+            let (rem, parsed) = rcpt(&out).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(parsed, command);
+        }
+    }
+
+    #[test]
+    fn test_mail_utf8() {
+        let test = "MAIL FROM:<用户@例子.广告> SMTPUTF8\r\n".as_bytes();
+
+        assert!(mail(test).is_err());
+
+        let (rem, parsed) = mail_utf8(test).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            parsed,
+            Command::Mail {
+                reverse_path: ReversePath::Path(Path {
+                    source_route: vec![],
+                    mailbox: Mailbox::new(
+                        "用户",
+                        DomainOrAddress::Domain(Domain::new("xn--fsqu00a.xn--4rr70v").unwrap())
+                    )
+                    .unwrap(),
+                }),
+                parameters: vec![Parameter::SmtpUtf8],
+            }
+        );
+        assert!(parsed.requires_smtputf8());
+
+        let (_, parsed) = mail(b"MAIL FROM:<a@example.com> smtputf8\r\n").unwrap();
+        assert!(parsed.requires_smtputf8());
+
+        let (_, parsed) = mail(b"MAIL FROM:<a@example.com> SMTPUTF8=x\r\n").unwrap();
+        assert!(!parsed.requires_smtputf8());
+    }
+
+    #[test]
+    fn test_command_utf8() {
+        let tests = [
+            "MAIL FROM:<用户@例子.广告> SMTPUTF8\r\n",
+            "MAIL FROM:<\"Jöhn Döe\"@example.com>\r\n",
+            "RCPT TO:<θσερ@εχαμπλε.ψομ>\r\n",
+            "RCPT TO:<Postmaster@bücher.example>\r\n",
+            "RCPT TO:<@bücher.example:ü@[192.0.2.1]>\r\n",
+            "VRFY Jöhn\r\n",
+            "EXPN \"Grüße an alle\"\r\n",
+            "MAIL FROM:<a@example.com> X-NAME=Jöhn\r\n",
+            "RCPT TO:<a@example.com> X-NAME=Jöhn\r\n",
+            "HELP Grüße\r\n",
+            "NOOP \"Grüße an alle\"\r\n",
+        ];
+
+        for test in tests {
+            assert!(command(test.as_bytes()).is_err(), "{}", test);
+
+            let (rem, parsed) = command_utf8(test.as_bytes()).unwrap();
+            assert!(rem.is_empty());
+            assert!(parsed.requires_smtputf8());
+
+            let mut out = Vec::new();
+            parsed.serialize(&mut out).unwrap();
+            assert_eq!(out, test.as_bytes());
+        }
+
+        // ASCII commands are parsed as before.
+        let (_, parsed) = command_utf8(b"RCPT TO:<a.b@example.com>\r\n").unwrap();
+        assert!(!parsed.requires_smtputf8());
+
+        // Malformed UTF-8 is rejected.
+        assert!(command_utf8(b"RCPT TO:<\xff@example.com>\r\n").is_err());
+        assert!(command_utf8(b"RCPT TO:<a@example.com> X-NAME=\xff\r\n").is_err());
+
+        // C1 control characters are rejected.
+        for test in [
+            "MAIL FROM:<a\u{85}b@example.com>\r\n",
+            "MAIL FROM:<\"a\u{85}b\"@example.com>\r\n",
+            "VRFY a\u{9f}b\r\n",
+            "NOOP \"a\u{80}b\"\r\n",
+        ] {
+            assert!(command_utf8(test.as_bytes()).is_err(), "{:?}", test);
+        }
+    }
 }
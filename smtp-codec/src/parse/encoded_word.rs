@@ -0,0 +1,234 @@
+//! Encoded-words in message headers (RFC 2047)
+
+use nom::{
+    bytes::streaming::{tag, take_while1},
+    combinator::{all_consuming, map_opt, map_res},
+    sequence::tuple,
+    IResult,
+};
+
+use crate::encoded_word::Encoding;
+
+/// encoded-word = "=?" charset "?" encoding "?" encoded-text "?="
+///
+/// The charset may carry a language tag, i.e., "charset*language" (RFC 2231, Section 5).
+///
+/// Returns the decoded text. Fails when the charset is unknown or the encoded-text is
+/// malformed.
+pub fn encoded_word(input: &[u8]) -> IResult<&[u8], String> {
+    let parser = tuple((
+        tag(b"=?"),
+        map_res(token, std::str::from_utf8),
+        tag(b"?"),
+        map_opt(token, |encoding| match encoding {
+            b"B" | b"b" => Some(Encoding::B),
+            b"Q" | b"q" => Some(Encoding::Q),
+            _ => None,
+        }),
+        tag(b"?"),
+        encoded_text,
+        tag(b"?="),
+    ));
+
+    map_opt(parser, |(_, charset, _, encoding, _, encoded_text, _)| {
+        let bytes = match encoding {
+            Encoding::B => decode_b(encoded_text)?,
+            Encoding::Q => decode_q(encoded_text)?,
+        };
+
+        let charset = charset.split('*').next().unwrap_or_default();
+        let charset = encoding_rs::Encoding::for_label(charset.as_bytes())?;
+
+        Some(charset.decode_without_bom_handling(&bytes).0.into_owned())
+    })(input)
+}
+
+/// Decode all encoded-words in an (unfolded) unstructured value, e.g., of a "Subject".
+///
+/// Encoded-words must be separated from other text by white space. White space between two
+/// adjacent encoded-words is removed. Malformed encoded-words or encoded-words in an unknown
+/// charset are left as is.
+pub fn decode_unstructured(value: &str) -> String {
+    let is_wsp = |character: char| matches!(character, ' ' | '\t');
+
+    let mut decoded = String::with_capacity(value.len());
+    let mut previous_was_encoded_word = false;
+    let mut rest = value;
+
+    while !rest.is_empty() {
+        let (white_space, tail) = rest.split_at(rest.find(|c| !is_wsp(c)).unwrap_or(rest.len()));
+        let (word, tail) = tail.split_at(tail.find(is_wsp).unwrap_or(tail.len()));
+
+        match all_consuming(encoded_word)(word.as_bytes()) {
+            Ok((_, text)) => {
+                if !previous_was_encoded_word {
+                    decoded.push_str(white_space);
+                }
+                decoded.push_str(&text);
+                previous_was_encoded_word = true;
+            }
+            Err(_) => {
+                decoded.push_str(white_space);
+                decoded.push_str(word);
+                previous_was_encoded_word = false;
+            }
+        }
+
+        rest = tail;
+    }
+
+    decoded
+}
+
+/// token = 1*<Any CHAR except SPACE, CTLs, and especials>
+pub fn token(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|byte| matches!(byte, 33..=126) && !is_especial(byte))(input)
+}
+
+/// especials = "(" / ")" / "<" / ">" / "@" / "," / ";" / ":" /
+///             <"> / "/" / "[" / "]" / "?" / "." / "="
+pub fn is_especial(byte: u8) -> bool {
+    b"()<>@,;:\"/[]?.=".contains(&byte)
+}
+
+/// encoded-text = 1*<Any printable ASCII character other than "?" or SPACE>
+pub fn encoded_text(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|byte| matches!(byte, 33..=126) && byte != b'?')(input)
+}
+
+/// 4.1.  The "B" encoding
+///
+/// Padding is optional.
+fn decode_b(encoded_text: &[u8]) -> Option<Vec<u8>> {
+    let encoded_text = encoded_text
+        .strip_suffix(b"==")
+        .or_else(|| encoded_text.strip_suffix(b"="))
+        .unwrap_or(encoded_text);
+
+    if encoded_text.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded_text.len() / 4 * 3 + 2);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for byte in encoded_text {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(decoded)
+}
+
+/// 4.2.  The "Q" encoding
+fn decode_q(encoded_text: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded_text.len());
+    let mut bytes = encoded_text.iter();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'_' => decoded.push(b' '),
+            b'=' => {
+                let hex = [*bytes.next()?, *bytes.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            _ => decoded.push(*byte),
+        }
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_unstructured, encoded_word};
+    use crate::encoded_word::{encode, Encoding};
+
+    #[test]
+    fn test_encoded_word() {
+        let tests: &[(&[u8], &str)] = &[
+            (b"=?US-ASCII?Q?Keith_Moore?=", "Keith Moore"),
+            (b"=?ISO-8859-1?Q?Andr=E9?=", "André"),
+            (b"=?iso-8859-2?q?P=F8=EDli=B9?=", "Příliš"),
+            (b"=?UTF-8?B?R3LDvMOfZQ==?=", "Grüße"),
+            (b"=?UTF-8?B?R3LDvMOfZQ?=", "Grüße"),
+            (b"=?utf-8*de?b?R3LDvMOfZQ==?=", "Grüße"),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, got) = encoded_word(test).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(got, *expected);
+        }
+
+        let tests: &[&[u8]] = &[
+            b"=?UNKNOWN?Q?a?=",
+            b"=?UTF-8?X?a?=",
+            b"=?UTF-8?B?R?=",
+            b"=?UTF-8?B?R3L!?=",
+            b"=?UTF-8?Q?a=G0?=",
+            b"=?UTF-8?Q?a=?=",
+            b"=?UTF-8?Q??=",
+        ];
+
+        for test in tests {
+            assert!(encoded_word(test).is_err());
+        }
+    }
+
+    #[test]
+    fn test_decode_unstructured() {
+        // RFC 2047, Section 8
+        let tests = [
+            ("=?ISO-8859-1?Q?a?=", "a"),
+            ("=?ISO-8859-1?Q?a?= b", "a b"),
+            ("=?ISO-8859-1?Q?a?= =?ISO-8859-1?Q?b?=", "ab"),
+            ("=?ISO-8859-1?Q?a?=  =?ISO-8859-1?Q?b?=", "ab"),
+            ("=?ISO-8859-1?Q?a?=\t =?ISO-8859-1?Q?b?=", "ab"),
+            ("=?ISO-8859-1?Q?a_b?=", "a b"),
+            ("=?ISO-8859-1?Q?a?= =?ISO-8859-2?Q?_b?=", "a b"),
+            (
+                "=?ISO-8859-1?B?SWYgeW91IGNhbiByZWFkIHRoaXMgeW8=?= \
+                 =?ISO-8859-2?B?dSB1bmRlcnN0YW5kIHRoZSBleGFtcGxlLg==?=",
+                "If you can read this you understand the example.",
+            ),
+            // Not encoded-words
+            ("Hello  World", "Hello  World"),
+            ("a=?ISO-8859-1?Q?b?=", "a=?ISO-8859-1?Q?b?="),
+            ("=?UNKNOWN?Q?a?= =?ISO-8859-1?Q?b?=", "=?UNKNOWN?Q?a?= b"),
+            ("Re: =?UTF-8?Q?caf=C3=A9?= time", "Re: café time"),
+        ];
+
+        for (test, expected) in tests {
+            assert_eq!(decode_unstructured(test), expected);
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let text = "Grüße aus Köln! ( = ? _ ) ".repeat(10);
+
+        for encoding in [Encoding::B, Encoding::Q] {
+            let words = encode(&text, encoding);
+            assert_eq!(decode_unstructured(&words.join(" ")), text);
+            assert_eq!(decode_unstructured(&words.join("\t")), text);
+        }
+    }
+}
@@ -1,6 +1,4 @@
 //! Internet Message Format (RFC 5322)
-//!
-//! TODO: replace this with an IMF library, e.g. rustyknife?
 
 /// 3.2.1.  Quoted characters
 pub mod quoted_characters {
@@ -33,26 +31,112 @@ pub mod quoted_characters {
 
 /// 3.2.2.  Folding White Space and Comments
 pub mod folding_ws_and_comment {
-    use nom::IResult;
+    use abnf_core::streaming::CRLF;
+    use nom::{
+        branch::alt,
+        bytes::streaming::{tag, take_while, take_while1, take_while_m_n},
+        combinator::{opt, recognize},
+        error::{Error, ErrorKind},
+        multi::{many0, many1},
+        sequence::tuple,
+        IResult,
+    };
+
+    use super::{
+        obsolete::{is_obs_ctext, obs_fws},
+        quoted_characters::quoted_pair,
+    };
+
+    /// Comments may nest, but we must not recurse without bound on untrusted input.
+    pub const MAX_COMMENT_DEPTH: usize = 32;
+
+    /// WSP = SP / HTAB
+    pub fn is_wsp(byte: u8) -> bool {
+        byte == b' ' || byte == b'\t'
+    }
 
     /// Folding white space
     ///
     /// FWS = ([*WSP CRLF] 1*WSP) / obs-FWS
-    pub fn fws(_input: &[u8]) -> IResult<&[u8], &[u8]> {
-        unimplemented!()
+    ///
+    /// Note: obs-FWS is tried first because it always matches at least as much as the first
+    ///       alternative when the input starts with WSP.
+    pub fn fws(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        let parser = alt((
+            obs_fws,
+            recognize(tuple((
+                opt(tuple((take_while(is_wsp), CRLF))),
+                take_while1(is_wsp),
+            ))),
+        ));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
     }
 
-    // Printable US-ASCII characters not including "(", ")", or "\"
-    //
-    // ctext = %d33-39 / %d42-91 / %d93-126 / obs-ctext
+    /// Printable US-ASCII characters not including "(", ")", or "\"
+    ///
+    /// ctext = %d33-39 / %d42-91 / %d93-126 / obs-ctext
+    pub fn is_ctext(byte: u8) -> bool {
+        match byte {
+            33..=39 | 42..=91 | 93..=126 => true,
+            _ if is_obs_ctext(byte) => true,
+            _ => false,
+        }
+    }
+
+    /// ccontent = ctext / quoted-pair / comment
+    pub fn ccontent(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        ccontent_at(input, 1)
+    }
+
+    /// comment = "(" *([FWS] ccontent) [FWS] ")"
+    ///
+    /// Note: Comments nested deeper than `MAX_COMMENT_DEPTH` are rejected with a failure.
+    pub fn comment(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        comment_at(input, 1)
+    }
+
+    fn ccontent_at(input: &[u8], depth: usize) -> IResult<&[u8], &[u8]> {
+        let parser = alt((take_while_m_n(1, 1, is_ctext), quoted_pair, |input| {
+            comment_at(input, depth + 1)
+        }));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
+    }
+
+    fn comment_at(input: &[u8], depth: usize) -> IResult<&[u8], &[u8]> {
+        tag(b"(")(input)?;
 
-    // ccontent = ctext / quoted-pair / comment
+        if depth > MAX_COMMENT_DEPTH {
+            return Err(nom::Err::Failure(Error::new(input, ErrorKind::TooLarge)));
+        }
+
+        let parser = tuple((
+            tag(b"("),
+            many0(tuple((opt(fws), |input| ccontent_at(input, depth)))),
+            opt(fws),
+            tag(b")"),
+        ));
 
-    // comment = "(" *([FWS] ccontent) [FWS] ")"
+        let (remaining, parsed) = recognize(parser)(input)?;
 
-    /// CFWS = (1*([fws] comment) [fws]) / FWS
-    pub fn cfws(_input: &[u8]) -> IResult<&[u8], &[u8]> {
-        unimplemented!()
+        Ok((remaining, parsed))
+    }
+
+    /// CFWS = (1*([FWS] comment) [FWS]) / FWS
+    pub fn cfws(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        let parser = alt((
+            recognize(tuple((many1(tuple((opt(fws), comment))), opt(fws)))),
+            fws,
+        ));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
     }
 }
 
@@ -61,9 +145,9 @@ pub mod atom {
     use abnf_core::streaming::{is_ALPHA, is_DIGIT};
     use nom::{
         bytes::streaming::{tag, take_while1},
-        combinator::{opt, recognize},
+        combinator::{map_res, opt, recognize},
         multi::many0,
-        sequence::tuple,
+        sequence::{delimited, tuple},
         IResult,
     };
 
@@ -119,6 +203,15 @@ pub mod atom {
         Ok((remaining, parsed))
     }
 
+    /// Same as [`atom`], but returns the atext without CFWS.
+    pub fn atom_content(input: &[u8]) -> IResult<&[u8], &str> {
+        delimited(
+            opt(cfws),
+            map_res(take_while1(is_atext), std::str::from_utf8),
+            opt(cfws),
+        )(input)
+    }
+
     // Special characters that do not appear in atext.
     //
     // specials = "(" / ")" /
@@ -137,9 +230,9 @@ pub mod quoted_strings {
     use nom::{
         branch::alt,
         bytes::streaming::take_while_m_n,
-        combinator::{opt, recognize},
+        combinator::{map, opt, recognize},
         multi::many0,
-        sequence::tuple,
+        sequence::{delimited, tuple},
         IResult,
     };
 
@@ -184,187 +277,479 @@ pub mod quoted_strings {
 
         Ok((remaining, parsed))
     }
+
+    /// Same as [`quoted_string`], but returns the content without CFWS and DQUOTEs.
+    ///
+    /// The content is unfolded and quoted-pairs are unescaped.
+    pub fn quoted_string_content(input: &[u8]) -> IResult<&[u8], String> {
+        let parser = delimited(
+            tuple((opt(cfws), DQUOTE)),
+            recognize(tuple((many0(tuple((opt(fws), qcontent))), opt(fws)))),
+            tuple((DQUOTE, opt(cfws))),
+        );
+
+        map(parser, |content: &[u8]| {
+            let mut unescaped = Vec::with_capacity(content.len());
+            let mut bytes = content.iter().copied().peekable();
+
+            while let Some(byte) = bytes.next() {
+                match byte {
+                    b'\\' => unescaped.extend(bytes.next()),
+                    b'\r' if bytes.peek() == Some(&b'\n') => {
+                        bytes.next();
+                    }
+                    _ => unescaped.push(byte),
+                }
+            }
+
+            String::from_utf8_lossy(&unescaped).into_owned()
+        })(input)
+    }
 }
 
 /// 3.2.5.  Miscellaneous Tokens
 pub mod miscellaneous {
-    use nom::{branch::alt, IResult};
+    use abnf_core::streaming::is_VCHAR;
+    use nom::{
+        branch::alt,
+        bytes::streaming::{tag, take_while, take_while1},
+        combinator::{all_consuming, map, opt, recognize},
+        multi::many0,
+        sequence::tuple,
+        IResult,
+    };
 
-    use super::{atom::atom, quoted_strings::quoted_string};
+    use super::{
+        atom::{atom, atom_content},
+        folding_ws_and_comment::{cfws, fws, is_wsp},
+        quoted_strings::{quoted_string, quoted_string_content},
+    };
+    use crate::parse::encoded_word::encoded_word;
 
     /// word = atom / quoted-string
     pub fn word(input: &[u8]) -> IResult<&[u8], &[u8]> {
         alt((atom, quoted_string))(input)
     }
 
-    // phrase = 1*word / obs-phrase
-    // ...
+    /// Same as [`word`], but returns the content of the atom or quoted-string.
+    pub fn word_content(input: &[u8]) -> IResult<&[u8], String> {
+        alt((map(atom_content, str::to_owned), quoted_string_content))(input)
+    }
 
-    // unstructured = (*([FWS] VCHAR) *WSP) / obs-unstruct
-    // ...
+    /// phrase = 1*word / obs-phrase
+    ///
+    /// obs-phrase = word *(word / "." / CFWS)
+    ///
+    /// Returns the words separated by single spaces. Comments are removed.
+    ///
+    /// Atoms that are encoded-words (RFC 2047) are decoded. Adjacent encoded-words are not
+    /// separated by a space.
+    pub fn phrase(input: &[u8]) -> IResult<&[u8], String> {
+        let mut parser = tuple((
+            phrase_word,
+            many0(alt((
+                map(phrase_word, Some),
+                map(tag(b"."), |_| Some((".".to_owned(), false))),
+                map(cfws, |_| None),
+            ))),
+        ));
+
+        let (remaining, (first, rest)) = parser(input)?;
+
+        let (mut phrase, mut previous_was_encoded_word) = first;
+        for (word, is_encoded_word) in rest.into_iter().flatten() {
+            if word != "." && !(previous_was_encoded_word && is_encoded_word) {
+                phrase.push(' ');
+            }
+            phrase.push_str(&word);
+            previous_was_encoded_word = is_encoded_word;
+        }
+
+        Ok((remaining, phrase))
+    }
+
+    /// Same as [`word_content`], but decodes encoded-words. Also returns whether the word was
+    /// an encoded-word.
+    fn phrase_word(input: &[u8]) -> IResult<&[u8], (String, bool)> {
+        alt((
+            map(atom_content, |atom| {
+                match all_consuming(encoded_word)(atom.as_bytes()) {
+                    Ok((_, decoded)) => (decoded, true),
+                    Err(_) => (atom.to_owned(), false),
+                }
+            }),
+            map(quoted_string_content, |content| (content, false)),
+        ))(input)
+    }
+
+    /// unstructured = (*([FWS] VCHAR) *WSP) / obs-unstruct
+    ///
+    /// Note: obs-unstruct is handled by `obsolete::obs_unstruct`.
+    pub fn unstructured(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        let parser = tuple((
+            many0(tuple((opt(fws), take_while1(is_VCHAR)))),
+            take_while(is_wsp),
+        ));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
+    }
 }
 
 /// 3.3.  Date and Time Specification
 pub mod datetime {
+    use std::str::FromStr;
+
     use abnf_core::streaming::is_DIGIT;
     use nom::{
         branch::alt,
         bytes::streaming::{tag, tag_no_case, take_while_m_n},
-        combinator::{opt, recognize},
-        sequence::tuple,
+        combinator::{map_res, opt, value, verify},
+        sequence::{delimited, preceded, tuple},
         IResult,
     };
 
-    use super::folding_ws_and_comment::{cfws, fws};
-
-    // date-time = [ day-of-week "," ] date time [CFWS]
-    pub fn date_time(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = tuple((opt(tuple((day_of_week, tag(b",")))), date, time, opt(cfws)));
-
-        let (remaining, parsed) = recognize(parser)(input)?;
+    use super::{
+        folding_ws_and_comment::{cfws, fws},
+        obsolete::{
+            obs_day, obs_day_of_week, obs_hour, obs_minute, obs_second, obs_year, obs_zone,
+        },
+    };
+    use crate::imf::{DateTime, DayOfWeek, InvalidDateTime, Month};
 
-        Ok((remaining, parsed))
+    /// date-time = [ day-of-week "," ] date time [CFWS]
+    ///
+    /// The date-time must be valid according to [`DateTime::validate`].
+    pub fn date_time(input: &[u8]) -> IResult<&[u8], DateTime> {
+        map_res(date_time_unchecked, validate)(input)
     }
 
-    // day-of-week = ([FWS] day-name) / obs-day-of-week
-    pub fn day_of_week(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = tuple((opt(fws), day_name));
-
-        let (remaining, parsed) = recognize(parser)(input)?;
+    /// Same as [`date_time`], but also accepts the obsolete syntax (RFC 5322, 4.3), e.g.,
+    /// two-digit years and zone names.
+    pub fn obs_date_time(input: &[u8]) -> IResult<&[u8], DateTime> {
+        map_res(obs_date_time_unchecked, validate)(input)
+    }
 
-        Ok((remaining, parsed))
+    /// Same as [`date_time`], but without validation.
+    pub(crate) fn date_time_unchecked(input: &[u8]) -> IResult<&[u8], DateTime> {
+        let mut parser = tuple((opt(tuple((day_of_week, tag(b",")))), date, time, opt(cfws)));
+
+        let (remaining, (maybe_day_of_week, (day, month, year), ((hour, minute, second), zone), _)) =
+            parser(input)?;
+
+        Ok((
+            remaining,
+            DateTime {
+                day_of_week: maybe_day_of_week.map(|(day_of_week, _)| day_of_week),
+                day,
+                month,
+                year,
+                hour,
+                minute,
+                second,
+                zone,
+            },
+        ))
     }
 
-    // day-name = "Mon" / "Tue" / "Wed" / "Thu" / "Fri" / "Sat" / "Sun"
-    pub fn day_name(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = alt((
-            tag_no_case(b"Mon"),
-            tag_no_case(b"Tue"),
-            tag_no_case(b"Wed"),
-            tag_no_case(b"Thu"),
-            tag_no_case(b"Fri"),
-            tag_no_case(b"Sat"),
-            tag_no_case(b"Sun"),
+    /// Same as [`obs_date_time`], but without validation.
+    ///
+    /// Note: The obsolete forms are supersets of the other alternatives.
+    pub(crate) fn obs_date_time_unchecked(input: &[u8]) -> IResult<&[u8], DateTime> {
+        let mut parser = tuple((
+            opt(tuple((obs_day_of_week, tag(b",")))),
+            obs_day,
+            month,
+            obs_year,
+            obs_hour,
+            tag(b":"),
+            obs_minute,
+            opt(preceded(tag(b":"), obs_second)),
+            obs_zone,
+            opt(cfws),
         ));
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+        let (remaining, (maybe_day_of_week, day, month, year, hour, _, minute, second, zone, _)) =
+            parser(input)?;
+
+        Ok((
+            remaining,
+            DateTime {
+                day_of_week: maybe_day_of_week.map(|(day_of_week, _)| day_of_week),
+                day,
+                month,
+                year,
+                hour,
+                minute,
+                second,
+                zone,
+            },
+        ))
+    }
 
-        Ok((remaining, parsed))
+    fn validate(date_time: DateTime) -> Result<DateTime, InvalidDateTime> {
+        date_time.validate().map(|_| date_time)
     }
 
-    // date = day month year
-    pub fn date(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = tuple((day, month, year));
+    /// day-of-week = ([FWS] day-name) / obs-day-of-week
+    pub fn day_of_week(input: &[u8]) -> IResult<&[u8], DayOfWeek> {
+        let mut parser = tuple((opt(fws), day_name));
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+        let (remaining, (_, day_name)) = parser(input)?;
 
-        Ok((remaining, parsed))
+        Ok((remaining, day_name))
     }
 
-    // day = ([FWS] 1*2DIGIT FWS) / obs-day
-    pub fn day(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = tuple((opt(fws), take_while_m_n(1, 2, is_DIGIT), fws));
+    /// day-name = "Mon" / "Tue" / "Wed" / "Thu" / "Fri" / "Sat" / "Sun"
+    pub fn day_name(input: &[u8]) -> IResult<&[u8], DayOfWeek> {
+        alt((
+            value(DayOfWeek::Monday, tag_no_case(b"Mon")),
+            value(DayOfWeek::Tuesday, tag_no_case(b"Tue")),
+            value(DayOfWeek::Wednesday, tag_no_case(b"Wed")),
+            value(DayOfWeek::Thursday, tag_no_case(b"Thu")),
+            value(DayOfWeek::Friday, tag_no_case(b"Fri")),
+            value(DayOfWeek::Saturday, tag_no_case(b"Sat")),
+            value(DayOfWeek::Sunday, tag_no_case(b"Sun")),
+        ))(input)
+    }
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+    /// date = day month year
+    pub fn date(input: &[u8]) -> IResult<&[u8], (u8, Month, u32)> {
+        tuple((day, month, year))(input)
+    }
 
-        Ok((remaining, parsed))
+    /// day = ([FWS] 1*2DIGIT FWS) / obs-day
+    pub fn day(input: &[u8]) -> IResult<&[u8], u8> {
+        delimited(opt(fws), digits(1, 2), fws)(input)
     }
 
-    // month = "Jan" / "Feb" / "Mar" / "Apr" / "May" / "Jun" / "Jul" / "Aug" / "Sep" / "Oct" / "Nov" / "Dec"
-    pub fn month(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = alt((
-            tag_no_case(b"Jan"),
-            tag_no_case(b"Feb"),
-            tag_no_case(b"Mar"),
-            tag_no_case(b"Apr"),
-            tag_no_case(b"May"),
-            tag_no_case(b"Jun"),
-            tag_no_case(b"Jul"),
-            tag_no_case(b"Aug"),
-            tag_no_case(b"Sep"),
-            tag_no_case(b"Oct"),
-            tag_no_case(b"Nov"),
-            tag_no_case(b"Dec"),
-        ));
+    /// month = "Jan" / "Feb" / "Mar" / "Apr" / "May" / "Jun" / "Jul" / "Aug" / "Sep" / "Oct" / "Nov" / "Dec"
+    pub fn month(input: &[u8]) -> IResult<&[u8], Month> {
+        alt((
+            value(Month::January, tag_no_case(b"Jan")),
+            value(Month::February, tag_no_case(b"Feb")),
+            value(Month::March, tag_no_case(b"Mar")),
+            value(Month::April, tag_no_case(b"Apr")),
+            value(Month::May, tag_no_case(b"May")),
+            value(Month::June, tag_no_case(b"Jun")),
+            value(Month::July, tag_no_case(b"Jul")),
+            value(Month::August, tag_no_case(b"Aug")),
+            value(Month::September, tag_no_case(b"Sep")),
+            value(Month::October, tag_no_case(b"Oct")),
+            value(Month::November, tag_no_case(b"Nov")),
+            value(Month::December, tag_no_case(b"Dec")),
+        ))(input)
+    }
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+    /// year = (FWS 4*DIGIT FWS) / obs-year
+    ///
+    /// Note: Years with more than 8 digits are not supported.
+    pub fn year(input: &[u8]) -> IResult<&[u8], u32> {
+        delimited(fws, digits(4, 8), fws)(input)
+    }
 
-        Ok((remaining, parsed))
+    /// Hour, minute, and (optional) second
+    pub type TimeOfDay = (u8, u8, Option<u8>);
+
+    /// time = time-of-day zone
+    pub fn time(input: &[u8]) -> IResult<&[u8], (TimeOfDay, i16)> {
+        tuple((time_of_day, zone))(input)
     }
 
-    // year = (FWS 4*DIGIT FWS) / obs-year
-    pub fn year(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = tuple((fws, take_while_m_n(4, 8, is_DIGIT), fws)); // FIXME: 4*?!
+    /// time-of-day = hour ":" minute [ ":" second ]
+    pub fn time_of_day(input: &[u8]) -> IResult<&[u8], TimeOfDay> {
+        let mut parser = tuple((hour, tag(b":"), minute, opt(tuple((tag(b":"), second)))));
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+        let (remaining, (hour, _, minute, maybe_second)) = parser(input)?;
 
-        Ok((remaining, parsed))
+        Ok((
+            remaining,
+            (hour, minute, maybe_second.map(|(_, second)| second)),
+        ))
     }
 
-    // time = time-of-day zone
-    pub fn time(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = tuple((time_of_day, zone));
+    /// hour = 2DIGIT / obs-hour
+    pub fn hour(input: &[u8]) -> IResult<&[u8], u8> {
+        digits(2, 2)(input)
+    }
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+    /// minute = 2DIGIT / obs-minute
+    pub fn minute(input: &[u8]) -> IResult<&[u8], u8> {
+        digits(2, 2)(input)
+    }
 
-        Ok((remaining, parsed))
+    /// second = 2DIGIT / obs-second
+    pub fn second(input: &[u8]) -> IResult<&[u8], u8> {
+        digits(2, 2)(input)
     }
 
-    // time-of-day = hour ":" minute [ ":" second ]
-    pub fn time_of_day(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        let parser = tuple((hour, tag(b":"), minute, opt(tuple((tag(b":"), second)))));
+    /// Offset from Universal Time in minutes.
+    ///
+    /// zone = (FWS ( "+" / "-" ) 4DIGIT) / obs-zone
+    pub fn zone(input: &[u8]) -> IResult<&[u8], i16> {
+        preceded(fws, zone_offset)(input)
+    }
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+    /// ( "+" / "-" ) 4DIGIT
+    pub(crate) fn zone_offset(input: &[u8]) -> IResult<&[u8], i16> {
+        let mut parser = tuple((
+            alt((value(1, tag(b"+")), value(-1, tag(b"-")))),
+            digits::<i16>(2, 2),
+            verify(digits::<i16>(2, 2), |minutes| *minutes < 60),
+        ));
 
-        Ok((remaining, parsed))
+        let (remaining, (sign, hours, minutes)) = parser(input)?;
+
+        Ok((remaining, sign * (hours * 60 + minutes)))
     }
 
-    // hour = 2DIGIT / obs-hour
-    pub fn hour(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        // FIXME: obs- forms must not be used in SMTP. Never?
+    pub(crate) fn digits<T: FromStr>(m: usize, n: usize) -> impl FnMut(&[u8]) -> IResult<&[u8], T> {
+        move |input| {
+            map_res(
+                map_res(take_while_m_n(m, n, is_DIGIT), std::str::from_utf8),
+                str::parse::<T>,
+            )(input)
+        }
+    }
+}
 
-        let parser = take_while_m_n(2, 2, is_DIGIT);
+/// 3.4.  Address Specification
+pub mod address {
+    use nom::{
+        branch::alt,
+        bytes::streaming::tag,
+        combinator::{map, opt, recognize},
+        multi::{many0, many1},
+        sequence::{delimited, preceded, tuple},
+        IResult,
+    };
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+    use super::{
+        addr_spec::addr_spec, folding_ws_and_comment::cfws, miscellaneous::phrase,
+        obsolete::obs_route,
+    };
+    use crate::imf::{AddrSpec, Address, Group, Mailbox};
 
-        Ok((remaining, parsed))
+    /// address = mailbox / group
+    pub fn address(input: &[u8]) -> IResult<&[u8], Address> {
+        alt((map(mailbox, Address::Mailbox), map(group, Address::Group)))(input)
     }
 
-    // minute = 2DIGIT / obs-minute
-    pub fn minute(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        // FIXME: obs- forms must not be used in SMTP. Never?
+    /// mailbox = name-addr / addr-spec
+    pub fn mailbox(input: &[u8]) -> IResult<&[u8], Mailbox> {
+        alt((
+            name_addr,
+            map(addr_spec, |addr_spec| Mailbox {
+                display_name: None,
+                addr_spec,
+            }),
+        ))(input)
+    }
 
-        let parser = take_while_m_n(2, 2, is_DIGIT);
+    /// name-addr = [display-name] angle-addr
+    pub fn name_addr(input: &[u8]) -> IResult<&[u8], Mailbox> {
+        let mut parser = tuple((opt(display_name), angle_addr));
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+        let (remaining, (display_name, addr_spec)) = parser(input)?;
 
-        Ok((remaining, parsed))
+        Ok((
+            remaining,
+            Mailbox {
+                display_name,
+                addr_spec,
+            },
+        ))
     }
 
-    // second = 2DIGIT / obs-second
-    pub fn second(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        // FIXME: obs- forms must not be used in SMTP. Never?
+    /// angle-addr = [CFWS] "<" addr-spec ">" [CFWS] / obs-angle-addr
+    ///
+    /// obs-angle-addr = [CFWS] "<" obs-route addr-spec ">" [CFWS]
+    pub fn angle_addr(input: &[u8]) -> IResult<&[u8], AddrSpec> {
+        delimited(
+            tuple((opt(cfws), tag(b"<"), opt(obs_route))),
+            addr_spec,
+            tuple((tag(b">"), opt(cfws))),
+        )(input)
+    }
 
-        let parser = take_while_m_n(2, 2, is_DIGIT);
+    /// group = display-name ":" [group-list] ";" [CFWS]
+    pub fn group(input: &[u8]) -> IResult<&[u8], Group> {
+        let mut parser = tuple((
+            display_name,
+            tag(b":"),
+            opt(group_list),
+            tag(b";"),
+            opt(cfws),
+        ));
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+        let (remaining, (display_name, _, mailboxes, _, _)) = parser(input)?;
 
-        Ok((remaining, parsed))
+        Ok((
+            remaining,
+            Group {
+                display_name,
+                mailboxes: mailboxes.unwrap_or_default(),
+            },
+        ))
     }
 
-    // zone = (FWS ( "+" / "-" ) 4DIGIT) / obs-zone
-    pub fn zone(input: &[u8]) -> IResult<&[u8], &[u8]> {
-        // FIXME: obs- forms must not be used in SMTP. Never?
+    /// display-name = phrase
+    pub fn display_name(input: &[u8]) -> IResult<&[u8], String> {
+        phrase(input)
+    }
 
-        let parser = tuple((
-            fws,
-            alt((tag(b"+"), tag(b"-"))),
-            take_while_m_n(4, 4, is_DIGIT),
-        ));
+    /// mailbox-list = (mailbox *("," mailbox)) / obs-mbox-list
+    ///
+    /// obs-mbox-list = *([CFWS] ",") mailbox *("," [mailbox / CFWS])
+    ///
+    /// Note: obs-mbox-list is a superset of the first alternative. Empty list elements are
+    ///       skipped.
+    pub fn mailbox_list(input: &[u8]) -> IResult<&[u8], Vec<Mailbox>> {
+        list(mailbox)(input)
+    }
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+    /// address-list = (address *("," address)) / obs-addr-list
+    ///
+    /// obs-addr-list = *([CFWS] ",") address *("," [address / CFWS])
+    ///
+    /// Note: obs-addr-list is a superset of the first alternative. Empty list elements are
+    ///       skipped.
+    pub fn address_list(input: &[u8]) -> IResult<&[u8], Vec<Address>> {
+        list(address)(input)
+    }
 
-        Ok((remaining, parsed))
+    /// group-list = mailbox-list / CFWS / obs-group-list
+    ///
+    /// obs-group-list = 1*([CFWS] ",") [CFWS]
+    pub fn group_list(input: &[u8]) -> IResult<&[u8], Vec<Mailbox>> {
+        alt((
+            mailbox_list,
+            map(recognize(many1(alt((cfws, tag(b","))))), |_| vec![]),
+        ))(input)
+    }
+
+    fn list<'a, O, F>(element: F) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<O>>
+    where
+        F: FnMut(&'a [u8]) -> IResult<&'a [u8], O> + Copy,
+    {
+        move |input| {
+            let mut parser = tuple((
+                many0(tuple((opt(cfws), tag(b",")))),
+                element,
+                many0(preceded(
+                    tag(b","),
+                    opt(alt((map(element, Some), map(cfws, |_| None)))),
+                )),
+            ));
+
+            let (remaining, (_, first, rest)) = parser(input)?;
+
+            let mut elements = vec![first];
+            elements.extend(rest.into_iter().flatten().flatten());
+
+            Ok((remaining, elements))
+        }
     }
 }
 
@@ -373,20 +758,54 @@ pub mod addr_spec {
     use nom::{
         branch::alt,
         bytes::streaming::{tag, take_while_m_n},
-        combinator::{opt, recognize},
-        multi::many0,
-        sequence::tuple,
+        combinator::{map, opt, recognize},
+        multi::{many0, separated_list1},
+        sequence::{delimited, tuple},
         IResult,
     };
 
     use super::{
-        atom::dot_atom,
+        atom::{atom_content, dot_atom},
         folding_ws_and_comment::{cfws, fws},
+        miscellaneous::word_content,
         obsolete::{obs_domain, obs_dtext, obs_local_part},
         quoted_strings::quoted_string,
     };
+    use crate::imf::AddrSpec;
+
+    /// addr-spec = local-part "@" domain
+    ///
+    /// Note: The obsolete forms of local-part and domain are supersets of the other
+    ///       alternatives and are used to extract the content.
+    pub fn addr_spec(input: &[u8]) -> IResult<&[u8], AddrSpec> {
+        let mut parser = tuple((local_part_content, tag(b"@"), domain_content));
+
+        let (remaining, (local_part, _, domain)) = parser(input)?;
 
-    // addr-spec = local-part "@" domain
+        Ok((remaining, AddrSpec { local_part, domain }))
+    }
+
+    /// Same as [`local_part`], but returns the content, i.e., without CFWS and with
+    /// quoted-strings unquoted.
+    ///
+    /// obs-local-part = word *("." word)
+    pub(crate) fn local_part_content(input: &[u8]) -> IResult<&[u8], String> {
+        map(separated_list1(tag(b"."), word_content), |words| {
+            words.join(".")
+        })(input)
+    }
+
+    /// Same as [`domain`], but returns the content, i.e., without CFWS and FWS.
+    ///
+    /// obs-domain = atom *("." atom)
+    pub(crate) fn domain_content(input: &[u8]) -> IResult<&[u8], String> {
+        alt((
+            domain_literal_content,
+            map(separated_list1(tag(b"."), atom_content), |atoms| {
+                atoms.join(".")
+            }),
+        ))(input)
+    }
 
     /// local-part = dot-atom / quoted-string / obs-local-part
     pub fn local_part(input: &[u8]) -> IResult<&[u8], &[u8]> {
@@ -444,50 +863,84 @@ pub mod addr_spec {
 
         Ok((remaining, parsed))
     }
+
+    /// Same as [`domain_literal`], but returns the literal (including the brackets) without
+    /// CFWS and FWS.
+    fn domain_literal_content(input: &[u8]) -> IResult<&[u8], String> {
+        let parser = delimited(
+            tuple((opt(cfws), tag(b"["))),
+            many0(tuple((opt(fws), dtext))),
+            tuple((opt(fws), tag(b"]"), opt(cfws))),
+        );
+
+        map(parser, |dtexts| {
+            let mut literal = String::from("[");
+            for (_, dtext) in dtexts {
+                literal.push_str(&String::from_utf8_lossy(dtext));
+            }
+            literal.push(']');
+            literal
+        })(input)
+    }
 }
 
 /// 3.6.4.  Identification Fields
 pub mod identification {
+    use abnf_core::streaming::CRLF;
     use nom::{
         branch::alt,
-        bytes::streaming::tag,
-        combinator::{opt, recognize},
-        multi::many0,
+        bytes::streaming::{tag, tag_no_case},
+        combinator::{map_res, opt, recognize},
+        multi::{many0, many1},
         sequence::{delimited, tuple},
         IResult,
     };
 
     use super::{
-        addr_spec::dtext,
+        addr_spec::{domain_content, dtext, local_part_content},
         atom::dot_atom_text,
         folding_ws_and_comment::cfws,
         obsolete::{obs_id_left, obs_id_right},
     };
+    use crate::imf::MessageId;
 
-    // message-id = "Message-ID:" msg-id CRLF
-    // ...
+    /// message-id = "Message-ID:" msg-id CRLF
+    pub fn message_id(input: &[u8]) -> IResult<&[u8], MessageId> {
+        delimited(tag_no_case(b"Message-ID:"), msg_id, CRLF)(input)
+    }
 
-    // in-reply-to = "In-Reply-To:" 1*msg-id CRLF
-    // ...
+    /// in-reply-to = "In-Reply-To:" 1*msg-id CRLF
+    pub fn in_reply_to(input: &[u8]) -> IResult<&[u8], Vec<MessageId>> {
+        delimited(tag_no_case(b"In-Reply-To:"), many1(msg_id), CRLF)(input)
+    }
 
-    // references = "References:" 1*msg-id CRLF
-    // ...
+    /// references = "References:" 1*msg-id CRLF
+    pub fn references(input: &[u8]) -> IResult<&[u8], Vec<MessageId>> {
+        delimited(tag_no_case(b"References:"), many1(msg_id), CRLF)(input)
+    }
 
-    /// msg-id = [cfws] "<" id-left "@" id-right ">" [cfws]
-    pub fn msg_id(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    /// msg-id = [CFWS] "<" id-left "@" id-right ">" [CFWS]
+    ///
+    /// Note: The obsolete forms of id-left and id-right are supersets of the other
+    ///       alternatives and are used to extract the content.
+    pub fn msg_id(input: &[u8]) -> IResult<&[u8], MessageId> {
         let parser = tuple((
             opt(cfws),
             tag(b"<"),
-            id_left,
+            // obs-id-left = local-part
+            local_part_content,
             tag(b"@"),
-            id_right,
+            // obs-id-right = domain
+            domain_content,
             tag(b">"),
             opt(cfws),
         ));
 
-        let (remaining, parsed) = recognize(parser)(input)?;
+        let (remaining, message_id) = map_res(parser, |(_, _, id_left, _, id_right, _, _)| {
+            MessageId::new_obs(id_left, id_right)
+        })(input)?;
 
-        Ok((remaining, parsed))
+        Ok((remaining, message_id))
     }
 
     /// id-left = dot-atom-text / obs-id-left
@@ -518,24 +971,199 @@ pub mod identification {
     }
 }
 
+/// 3.6.  Field Definitions
+pub mod fields {
+    use abnf_core::streaming::CRLF;
+    use nom::{
+        branch::alt,
+        bytes::streaming::{tag, take_while, take_while1},
+        combinator::{cut, map, map_res, opt, peek},
+        multi::many0,
+        sequence::terminated,
+        IResult,
+    };
+
+    use super::{
+        address::{address_list, mailbox, mailbox_list},
+        datetime::{date_time, obs_date_time},
+        folding_ws_and_comment::{cfws, is_wsp},
+        identification::{in_reply_to, message_id, references},
+        miscellaneous::unstructured,
+        obsolete::{obs_in_reply_to, obs_message_id, obs_references, obs_unstruct},
+    };
+    use crate::{
+        imf::{Field, FieldValue},
+        parse::encoded_word::decode_unstructured,
+    };
+
+    /// fields = *(trace
+    ///            *optional-field /
+    ///            *(resent-date /
+    ///             resent-from /
+    ///             resent-sender /
+    ///             resent-to /
+    ///             resent-cc /
+    ///             resent-bcc /
+    ///             resent-msg-id))
+    ///          *(orig-date /
+    ///            from /
+    ///            sender /
+    ///            reply-to /
+    ///            to /
+    ///            cc /
+    ///            bcc /
+    ///            message-id /
+    ///            in-reply-to /
+    ///            references /
+    ///            subject /
+    ///            comments /
+    ///            keywords /
+    ///            optional-field)
+    ///
+    /// Parses fields until the empty line that separates the header section from the body.
+    /// The empty line is not consumed, but must follow the fields, i.e., a malformed field
+    /// results in an error.
+    ///
+    /// Note: Neither the order nor the number of occurrences of fields is checked. A field
+    ///       with a known name but a malformed value results in a `nom::Err::Failure`.
+    pub fn fields(input: &[u8]) -> IResult<&[u8], Vec<Field>> {
+        terminated(many0(|input| field(input, false)), peek(CRLF))(input)
+    }
+
+    /// Same as [`fields`], but also accepts the obsolete syntax, e.g., white space between
+    /// the field name and the colon.
+    ///
+    /// obs-fields = *(obs-return /
+    ///                obs-received /
+    ///                obs-orig-date /
+    ///                ...
+    ///                obs-optional)
+    pub fn obs_fields(input: &[u8]) -> IResult<&[u8], Vec<Field>> {
+        terminated(many0(|input| field(input, true)), peek(CRLF))(input)
+    }
+
+    fn field(input: &[u8], obs: bool) -> IResult<&[u8], Field> {
+        let (rest, name) = map_res(field_name, std::str::from_utf8)(input)?;
+        // obs-optional = field-name *WSP ":" unstructured CRLF
+        let (rest, _) = match obs {
+            true => take_while(is_wsp)(rest)?,
+            false => (rest, &b""[..]),
+        };
+        let (rest, _) = tag(b":")(rest)?;
+
+        let unstructured = move |input| match obs {
+            true => obs_unstruct(input),
+            false => unstructured(input),
+        };
+        let text = move |input| map(unstructured, unfold)(input);
+
+        let (remaining, value) = match name.to_ascii_lowercase().as_str() {
+            "date" => cut(terminated(
+                map(
+                    move |input| match obs {
+                        true => obs_date_time(input),
+                        false => date_time(input),
+                    },
+                    FieldValue::Date,
+                ),
+                CRLF,
+            ))(rest)?,
+            "from" => cut(terminated(map(mailbox_list, FieldValue::From), CRLF))(rest)?,
+            "sender" => cut(terminated(map(mailbox, FieldValue::Sender), CRLF))(rest)?,
+            "reply-to" => cut(terminated(map(address_list, FieldValue::ReplyTo), CRLF))(rest)?,
+            "to" => cut(terminated(map(address_list, FieldValue::To), CRLF))(rest)?,
+            "cc" => cut(terminated(map(address_list, FieldValue::Cc), CRLF))(rest)?,
+            // bcc = "Bcc:" [address-list / CFWS] CRLF
+            "bcc" => cut(terminated(
+                map(
+                    opt(alt((address_list, map(cfws, |_| vec![])))),
+                    |addresses| FieldValue::Bcc(addresses.unwrap_or_default()),
+                ),
+                CRLF,
+            ))(rest)?,
+            // The identification fields are parsed including their field name.
+            "message-id" => cut(map(
+                move |input| match obs {
+                    true => obs_message_id(input),
+                    false => message_id(input),
+                },
+                FieldValue::MessageId,
+            ))(input)?,
+            "in-reply-to" => cut(map(
+                move |input| match obs {
+                    true => obs_in_reply_to(input),
+                    false => in_reply_to(input),
+                },
+                FieldValue::InReplyTo,
+            ))(input)?,
+            "references" => cut(map(
+                move |input| match obs {
+                    true => obs_references(input),
+                    false => references(input),
+                },
+                FieldValue::References,
+            ))(input)?,
+            "subject" => cut(terminated(
+                map(text, |text| FieldValue::Subject(decode_unstructured(&text))),
+                CRLF,
+            ))(rest)?,
+            _ => terminated(map(text, FieldValue::Unstructured), CRLF)(rest)?,
+        };
+
+        Ok((
+            remaining,
+            Field {
+                name: name.to_owned(),
+                raw: input[..input.len() - remaining.len()].to_vec(),
+                value,
+            },
+        ))
+    }
+
+    /// field-name = 1*ftext
+    pub fn field_name(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        take_while1(is_ftext)(input)
+    }
+
+    /// Printable US-ASCII characters not including ":".
+    ///
+    /// ftext = %d33-57 / %d59-126
+    pub fn is_ftext(byte: u8) -> bool {
+        matches!(byte, 33..=57 | 59..=126)
+    }
+
+    /// Remove all CRLFs (which are always followed by WSP in a folded value) and strip
+    /// leading and trailing white space.
+    fn unfold(value: &[u8]) -> String {
+        String::from_utf8_lossy(value)
+            .replace("\r\n", "")
+            .trim_matches(|c| c == ' ' || c == '\t')
+            .to_owned()
+    }
+}
+
 /// 4.1.  Miscellaneous Obsolete Tokens
 pub mod obsolete {
-    use abnf_core::streaming::{CR, LF};
+    use abnf_core::streaming::{is_DIGIT, is_VCHAR, CR, CRLF, LF};
     use nom::{
         branch::alt,
-        bytes::streaming::{tag, take_while_m_n},
-        combinator::recognize,
+        bytes::streaming::{tag, tag_no_case, take_while, take_while1, take_while_m_n},
+        combinator::{map, map_res, opt, recognize, value},
         multi::many0,
-        sequence::tuple,
+        sequence::{delimited, preceded, tuple},
         IResult,
     };
 
     use super::{
         addr_spec::{domain, local_part},
         atom::atom,
-        miscellaneous::word,
+        datetime::{day_name, digits, zone_offset},
+        folding_ws_and_comment::{cfws, fws, is_wsp},
+        identification::msg_id,
+        miscellaneous::{phrase, word},
         quoted_characters::quoted_pair,
     };
+    use crate::imf::{DayOfWeek, MessageId};
 
     /// US-ASCII control characters that do not include the carriage
     /// return, line feed, and white space characters
@@ -550,6 +1178,28 @@ pub mod obsolete {
         is_obs_no_ws_ctl(byte)
     }
 
+    /// obs-ctext = obs-NO-WS-CTL
+    pub fn is_obs_ctext(byte: u8) -> bool {
+        is_obs_no_ws_ctl(byte)
+    }
+
+    /// obs-utext = %d0 / obs-NO-WS-CTL / VCHAR
+    pub fn is_obs_utext(byte: u8) -> bool {
+        byte == 0x00 || is_obs_no_ws_ctl(byte) || is_VCHAR(byte)
+    }
+
+    /// obs-unstruct = *((*CR 1*(obs-utext / FWS)) / 1*LF) *CR
+    ///
+    /// Note: Bare CR and LF are not accepted, because they can not be told apart from the end
+    ///       of the field.
+    pub fn obs_unstruct(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        let parser = many0(alt((take_while1(is_obs_utext), fws)));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
+    }
+
     /// obs-qp = "\" (%d0 / obs-NO-WS-CTL / LF / CR)
     pub fn obs_qp(input: &[u8]) -> IResult<&[u8], &[u8]> {
         let parser = tuple((
@@ -567,8 +1217,138 @@ pub mod obsolete {
         Ok((remaining, parsed))
     }
 
+    // 4.2.  Obsolete Folding White Space (RFC 5322)
+
+    /// obs-FWS = 1*WSP *(CRLF 1*WSP)
+    pub fn obs_fws(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        let parser = tuple((
+            take_while1(is_wsp),
+            many0(tuple((CRLF, take_while1(is_wsp)))),
+        ));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
+    }
+
+    // 4.3.  Obsolete Date and Time (RFC 5322)
+
+    /// obs-day-of-week = [CFWS] day-name [CFWS]
+    pub fn obs_day_of_week(input: &[u8]) -> IResult<&[u8], DayOfWeek> {
+        delimited(opt(cfws), day_name, opt(cfws))(input)
+    }
+
+    /// obs-day = [CFWS] 1*2DIGIT [CFWS]
+    pub fn obs_day(input: &[u8]) -> IResult<&[u8], u8> {
+        delimited(opt(cfws), digits(1, 2), opt(cfws))(input)
+    }
+
+    /// obs-year = [CFWS] 2*DIGIT [CFWS]
+    ///
+    /// Two-digit years are interpreted as 2000-2049 and 1950-1999, three-digit years are
+    /// interpreted by adding 1900 (see RFC 5322, 4.3).
+    ///
+    /// Note: Years with more than 8 digits are not supported.
+    pub fn obs_year(input: &[u8]) -> IResult<&[u8], u32> {
+        let parser = delimited(
+            opt(cfws),
+            map_res(take_while_m_n(2, 8, is_DIGIT), std::str::from_utf8),
+            opt(cfws),
+        );
+
+        map_res(parser, |year: &str| {
+            year.parse::<u32>().map(|value| match year.len() {
+                2 if value < 50 => value + 2000,
+                2 | 3 => value + 1900,
+                _ => value,
+            })
+        })(input)
+    }
+
+    /// obs-hour = [CFWS] 2DIGIT [CFWS]
+    pub fn obs_hour(input: &[u8]) -> IResult<&[u8], u8> {
+        delimited(opt(cfws), digits(2, 2), opt(cfws))(input)
+    }
+
+    /// obs-minute = [CFWS] 2DIGIT [CFWS]
+    pub fn obs_minute(input: &[u8]) -> IResult<&[u8], u8> {
+        delimited(opt(cfws), digits(2, 2), opt(cfws))(input)
+    }
+
+    /// obs-second = [CFWS] 2DIGIT [CFWS]
+    pub fn obs_second(input: &[u8]) -> IResult<&[u8], u8> {
+        delimited(opt(cfws), digits(2, 2), opt(cfws))(input)
+    }
+
+    /// Offset from Universal Time in minutes.
+    ///
+    /// zone = (FWS ( "+" / "-" ) 4DIGIT) / obs-zone
+    ///
+    /// obs-zone = "UT" / "GMT" /     ; Universal Time
+    ///            "EST" / "EDT" /    ; Eastern:  - 5/ - 4
+    ///            "CST" / "CDT" /    ; Central:  - 6/ - 5
+    ///            "MST" / "MDT" /    ; Mountain: - 7/ - 6
+    ///            "PST" / "PDT" /    ; Pacific:  - 8/ - 7
+    ///            %d65-73 /          ; Military zones - "A"
+    ///            %d75-90 /          ; through "I" and "K"
+    ///            %d97-105 /         ; through "Z", both
+    ///            %d107-122          ; upper and lower case
+    ///
+    /// Note: This accepts CFWS before the zone, which is otherwise part of obs-minute or
+    ///       obs-second. Military zones are interpreted as "-0000" (see RFC 5322, 4.3).
+    pub fn obs_zone(input: &[u8]) -> IResult<&[u8], i16> {
+        fn is_military(byte: u8) -> bool {
+            matches!(byte, 65..=73 | 75..=90 | 97..=105 | 107..=122)
+        }
+
+        preceded(
+            opt(cfws),
+            alt((
+                zone_offset,
+                value(0, alt((tag_no_case(b"UT"), tag_no_case(b"GMT")))),
+                value(-5 * 60, tag_no_case(b"EST")),
+                value(-4 * 60, tag_no_case(b"EDT")),
+                value(-6 * 60, tag_no_case(b"CST")),
+                value(-5 * 60, tag_no_case(b"CDT")),
+                value(-7 * 60, tag_no_case(b"MST")),
+                value(-6 * 60, tag_no_case(b"MDT")),
+                value(-8 * 60, tag_no_case(b"PST")),
+                value(-7 * 60, tag_no_case(b"PDT")),
+                value(0, take_while_m_n(1, 1, is_military)),
+            )),
+        )(input)
+    }
+
     // 4.4.  Obsolete Addressing (RFC 5322)
 
+    /// obs-route = obs-domain-list ":"
+    pub fn obs_route(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        let parser = tuple((obs_domain_list, tag(b":")));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
+    }
+
+    /// obs-domain-list = *(CFWS / ",") "@" domain
+    ///                   *("," [CFWS] ["@" domain])
+    pub fn obs_domain_list(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        let parser = tuple((
+            many0(alt((cfws, tag(b",")))),
+            tag(b"@"),
+            domain,
+            many0(tuple((
+                tag(b","),
+                opt(cfws),
+                opt(tuple((tag(b"@"), domain))),
+            ))),
+        ));
+
+        let (remaining, parsed) = recognize(parser)(input)?;
+
+        Ok((remaining, parsed))
+    }
+
     /// obs-local-part = word *("." word)
     pub fn obs_local_part(input: &[u8]) -> IResult<&[u8], &[u8]> {
         let parser = tuple((word, many0(tuple((tag(b"."), word)))));
@@ -598,6 +1378,38 @@ pub mod obsolete {
 
     // 4.5.4.  Obsolete Identification Fields (RFC 5322)
 
+    /// obs-message-id = "Message-ID" *WSP ":" msg-id CRLF
+    pub fn obs_message_id(input: &[u8]) -> IResult<&[u8], MessageId> {
+        delimited(obs_field_name(b"Message-ID"), msg_id, CRLF)(input)
+    }
+
+    /// obs-in-reply-to = "In-Reply-To" *WSP ":" *(phrase / msg-id) CRLF
+    ///
+    /// Phrases are skipped.
+    pub fn obs_in_reply_to(input: &[u8]) -> IResult<&[u8], Vec<MessageId>> {
+        delimited(obs_field_name(b"In-Reply-To"), obs_msg_ids, CRLF)(input)
+    }
+
+    /// obs-references = "References" *WSP ":" *(phrase / msg-id) CRLF
+    ///
+    /// Phrases are skipped.
+    pub fn obs_references(input: &[u8]) -> IResult<&[u8], Vec<MessageId>> {
+        delimited(obs_field_name(b"References"), obs_msg_ids, CRLF)(input)
+    }
+
+    /// *(phrase / msg-id)
+    fn obs_msg_ids(input: &[u8]) -> IResult<&[u8], Vec<MessageId>> {
+        map(
+            many0(alt((map(msg_id, Some), map(phrase, |_| None)))),
+            |msg_ids| msg_ids.into_iter().flatten().collect(),
+        )(input)
+    }
+
+    /// name *WSP ":"
+    fn obs_field_name(name: &'static [u8]) -> impl FnMut(&[u8]) -> IResult<&[u8], &[u8]> {
+        move |input| recognize(tuple((tag_no_case(name), take_while(is_wsp), tag(b":"))))(input)
+    }
+
     /// obs-id-left = local-part
     pub fn obs_id_left(input: &[u8]) -> IResult<&[u8], &[u8]> {
         local_part(input)
@@ -608,3 +1420,582 @@ pub mod obsolete {
         domain(input)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        address::address_list,
+        datetime::{date_time, obs_date_time},
+        fields::{fields, obs_fields},
+        folding_ws_and_comment::{cfws, comment, fws, MAX_COMMENT_DEPTH},
+        identification::msg_id,
+        obsolete::obs_references,
+    };
+    use crate::{
+        encoded_word::{encode, Encoding},
+        imf::{
+            AddrSpec, Address, DateTime, DayOfWeek, FieldValue, Group, InvalidEnvelopeAddress,
+            Mailbox, MessageId, Month, RawField,
+        },
+        Command, Path, ReversePath,
+    };
+
+    fn mailbox(display_name: Option<&str>, local_part: &str, domain: &str) -> Mailbox {
+        Mailbox {
+            display_name: display_name.map(str::to_owned),
+            addr_spec: AddrSpec {
+                local_part: local_part.into(),
+                domain: domain.into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_fws() {
+        let tests: &[(&[u8], &[u8], &[u8])] = &[
+            (b" x", b" ", b"x"),
+            (b" \t x", b" \t ", b"x"),
+            (b"\r\n x", b"\r\n ", b"x"),
+            (b"  \r\n\tx", b"  \r\n\t", b"x"),
+            // obs-FWS
+            (b" \r\n \r\n x", b" \r\n \r\n ", b"x"),
+            // A CRLF that is not followed by WSP ends the field.
+            (b" \r\nx", b" ", b"\r\nx"),
+        ];
+
+        for (test, expected, expected_rem) in tests {
+            let (rem, got) = fws(test).unwrap();
+            assert_eq!(got, *expected);
+            assert_eq!(rem, *expected_rem);
+        }
+
+        assert!(fws(b"x").is_err());
+        assert!(fws(b"\r\nx").is_err());
+    }
+
+    #[test]
+    fn test_comment() {
+        let tests: &[(&[u8], &[u8], &[u8])] = &[
+            (b"()x", b"()", b"x"),
+            (b"(a b)x", b"(a b)", b"x"),
+            (b"(a (nested (comment)))x", b"(a (nested (comment)))", b"x"),
+            (b"(quoted \\) pair)x", b"(quoted \\) pair)", b"x"),
+            (b"(folded\r\n comment)x", b"(folded\r\n comment)", b"x"),
+        ];
+
+        for (test, expected, expected_rem) in tests {
+            let (rem, got) = comment(test).unwrap();
+            assert_eq!(got, *expected);
+            assert_eq!(rem, *expected_rem);
+        }
+
+        assert!(comment(b"(unbalanced))").is_ok());
+        assert!(comment(b"(unbalanced x").is_err());
+    }
+
+    #[test]
+    fn test_comment_depth() {
+        let nested = |depth: usize| {
+            let mut input = vec![b'('; depth];
+            input.extend(vec![b')'; depth]);
+            input.push(b'x');
+            input
+        };
+
+        assert!(comment(&nested(MAX_COMMENT_DEPTH)).is_ok());
+        assert!(matches!(
+            comment(&nested(MAX_COMMENT_DEPTH + 1)),
+            Err(nom::Err::Failure(_))
+        ));
+        // Must not overflow the stack.
+        assert!(comment(&nested(1_000_000)).is_err());
+    }
+
+    #[test]
+    fn test_cfws() {
+        let tests: &[(&[u8], &[u8], &[u8])] = &[
+            (b" x", b" ", b"x"),
+            (b"(comment)x", b"(comment)", b"x"),
+            (b" (a) (b) x", b" (a) (b) ", b"x"),
+            (b"\r\n (a)\r\n x", b"\r\n (a)\r\n ", b"x"),
+        ];
+
+        for (test, expected, expected_rem) in tests {
+            let (rem, got) = cfws(test).unwrap();
+            assert_eq!(got, *expected);
+            assert_eq!(rem, *expected_rem);
+        }
+    }
+
+    #[test]
+    fn test_date_time() {
+        let (rem, got) =
+            date_time(b"Thu, 21 May 1998\r\n    05:33:29 -0700 (PDT)\r\n\r\n").unwrap();
+        assert_eq!(
+            got,
+            DateTime {
+                day_of_week: Some(DayOfWeek::Thursday),
+                day: 21,
+                month: Month::May,
+                year: 1998,
+                hour: 5,
+                minute: 33,
+                second: Some(29),
+                zone: -420,
+            }
+        );
+        assert_eq!(rem, b"\r\n\r\n");
+
+        let (_, got) = date_time(b"1 Jan 2024 00:00 +0130\r\n\r\n").unwrap();
+        assert_eq!(got.day_of_week, None);
+        assert_eq!(got.second, None);
+        assert_eq!(got.zone, 90);
+
+        let tests: &[&[u8]] = &[
+            // Obsolete syntax
+            b"Thu, 21 May 98 05:33:29 -0700\r\n\r\n",
+            b"Thu, 21 May 1998 05:33:29 PDT\r\n\r\n",
+            // Invalid
+            b"Thu, 29 Feb 2023 05:33:29 -0700\r\n\r\n",
+            b"Fri, 21 May 1998 05:33:29 -0700\r\n\r\n",
+            b"Thu, 21 May 1998 24:00:00 -0700\r\n\r\n",
+            b"Thu, 21 May 1998 05:33:29 -0760\r\n\r\n",
+            b"1 Jan 1899 00:00 +0000\r\n\r\n",
+        ];
+
+        for test in tests {
+            assert!(date_time(test).is_err());
+        }
+
+        let (_, got) = date_time(b"Thu, 29 Feb 2024 23:59:60 +0000\r\n\r\n").unwrap();
+        assert_eq!(got.second, Some(60));
+    }
+
+    #[test]
+    fn test_obs_date_time() {
+        let tests: &[(&[u8], u32, i16)] = &[
+            (b"Thu, 21 May 1998 05:33:29 -0700\r\n\r\n", 1998, -420),
+            (b"Thu, 21 May 98 05:33:29 -0700\r\n\r\n", 1998, -420),
+            (b"Wed, 21 May 08 05:33:29 -0700\r\n\r\n", 2008, -420),
+            (b"Thu, 21 May 098 05:33:29 -0700\r\n\r\n", 1998, -420),
+            (b"Thu, 21 May 1998 05:33:29 PDT\r\n\r\n", 1998, -420),
+            (b"Thu, 21 May 1998 05:33:29 gmt\r\n\r\n", 1998, 0),
+            (b"Thu, 21 May 1998 05:33:29 Z\r\n\r\n", 1998, 0),
+            (
+                b"Thu (day) , 21 (day) May 1998 05 : 33 : 29 (second) -0700\r\n\r\n",
+                1998,
+                -420,
+            ),
+        ];
+
+        for (test, year, zone) in tests {
+            let (rem, got) = obs_date_time(test).unwrap();
+            assert_eq!(rem, b"\r\n\r\n");
+            assert_eq!(got.year, *year);
+            assert_eq!(got.zone, *zone);
+            assert_eq!((got.day, got.month, got.hour), (21, Month::May, 5));
+        }
+
+        assert!(obs_date_time(b"Fri, 21 May 98 05:33:29 -0700\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_address_list() {
+        let tests: &[(&[u8], Vec<Address>)] = &[
+            (
+                b"jdoe@example.org\r\n",
+                vec![Address::Mailbox(mailbox(None, "jdoe", "example.org"))],
+            ),
+            (
+                b"\"Joe Q. Public\" <john.q.public@example.com>\r\n",
+                vec![Address::Mailbox(mailbox(
+                    Some("Joe Q. Public"),
+                    "john.q.public",
+                    "example.com",
+                ))],
+            ),
+            (
+                b"Pete(A nice \\) chap) <pete(his account)@silly.test(his host)>\r\n",
+                vec![Address::Mailbox(mailbox(Some("Pete"), "pete", "silly.test"))],
+            ),
+            (
+                b"\"quoted \\\"local\\\" part\"@[192.0.2.1]\r\n",
+                vec![Address::Mailbox(mailbox(
+                    None,
+                    "quoted \"local\" part",
+                    "[192.0.2.1]",
+                ))],
+            ),
+            (
+                b"A Group:Ed Jones <c@a.test>,joe@where.test,John <jdoe@one.test>;, Undisclosed recipients:;\r\n",
+                vec![
+                    Address::Group(Group {
+                        display_name: "A Group".into(),
+                        mailboxes: vec![
+                            mailbox(Some("Ed Jones"), "c", "a.test"),
+                            mailbox(None, "joe", "where.test"),
+                            mailbox(Some("John"), "jdoe", "one.test"),
+                        ],
+                    }),
+                    Address::Group(Group {
+                        display_name: "Undisclosed recipients".into(),
+                        mailboxes: vec![],
+                    }),
+                ],
+            ),
+            // Obsolete syntax
+            (
+                b"Joe Q. Public <@relay.test,@other.test:john . q . public @ example . com>\r\n",
+                vec![Address::Mailbox(mailbox(
+                    Some("Joe Q. Public"),
+                    "john.q.public",
+                    "example.com",
+                ))],
+            ),
+            (
+                b", ,a@b.test,,\r\n (c) , c@d.test,\r\n",
+                vec![
+                    Address::Mailbox(mailbox(None, "a", "b.test")),
+                    Address::Mailbox(mailbox(None, "c", "d.test")),
+                ],
+            ),
+            (
+                b"Group: , (empty) ,;\r\n",
+                vec![Address::Group(Group {
+                    display_name: "Group".into(),
+                    mailboxes: vec![],
+                })],
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let input = [*test, b"\r\n"].concat();
+            let (rem, got) = address_list(&input).unwrap();
+            assert_eq!(rem, b"\r\n\r\n");
+            assert_eq!(got, *expected);
+        }
+    }
+
+    #[test]
+    fn test_to_envelope() {
+        let tests: &[(&[u8], &str)] = &[
+            (b"john.doe@example.com", "john.doe@example.com"),
+            (
+                b"john . doe (comment) @ example . com",
+                "john.doe@example.com",
+            ),
+            (b"\"john.doe\"@example.com", "john.doe@example.com"),
+            (b"\"john doe\"@example.com", "\"john doe\"@example.com"),
+            (b"john.\"doe\"@example.com", "john.doe@example.com"),
+            (
+                b"\"a\\\"b\\\\c\"@example.com",
+                "\"a\\\"b\\\\c\"@example.com",
+            ),
+            (b"\"john..doe\"@example.com", "\"john..doe\"@example.com"),
+            (b"john@[192.0.2.1]", "john@[192.0.2.1]"),
+            (b"john@[192.000.002.001]", "john@[192.0.2.1]"),
+            (b"john@[IPv6:2001:db8::1]", "john@[IPv6:2001:db8::1]"),
+            (b"john@[x-tag:content]", "john@[x-tag:content]"),
+        ];
+
+        for (test, expected) in tests {
+            let input = [*test, b"\r\n\r\n"].concat();
+            let (_, addresses) = address_list(&input).unwrap();
+            let got = addresses[0].mailboxes()[0].addr_spec.to_envelope().unwrap();
+            assert_eq!(got.to_string(), *expected);
+            assert!(!got.requires_smtputf8());
+
+            // Can be used in MAIL as is.
+            let command = Command::Mail {
+                reverse_path: ReversePath::Path(Path::from(got)),
+                parameters: vec![],
+            };
+            let mut out = Vec::new();
+            command.serialize(&mut out).unwrap();
+            assert_eq!(out, format!("MAIL FROM:<{}>\r\n", expected).as_bytes());
+        }
+
+        // A non-ASCII local part (RFC 6532) is converted, but requires SMTPUTF8.
+        let addr_spec = AddrSpec {
+            local_part: "j\u{f6}rg".into(),
+            domain: "example.com".into(),
+        };
+        assert!(addr_spec.to_envelope().unwrap().requires_smtputf8());
+
+        let tests = [
+            (
+                "john\tdoe",
+                "example.com",
+                InvalidEnvelopeAddress::LocalPart,
+            ),
+            ("john\r\n", "example.com", InvalidEnvelopeAddress::LocalPart),
+            (
+                "john",
+                "under_score.example",
+                InvalidEnvelopeAddress::Domain,
+            ),
+            ("john", "-example.com", InvalidEnvelopeAddress::Domain),
+            (
+                "john",
+                "[192.0.2.256]",
+                InvalidEnvelopeAddress::AddressLiteral,
+            ),
+            (
+                "john",
+                "[IPv6:2001:db8::g]",
+                InvalidEnvelopeAddress::AddressLiteral,
+            ),
+            (
+                "john",
+                "[something]",
+                InvalidEnvelopeAddress::AddressLiteral,
+            ),
+            ("john", "[1.2.3]", InvalidEnvelopeAddress::AddressLiteral),
+        ];
+
+        for (local_part, domain, expected) in tests {
+            let addr_spec = AddrSpec {
+                local_part: local_part.into(),
+                domain: domain.into(),
+            };
+            assert_eq!(addr_spec.to_envelope(), Err(expected));
+        }
+    }
+
+    #[test]
+    fn test_msg_id() {
+        let tests: &[(&[u8], &str, &str)] = &[
+            (
+                b"<1234@local.machine.example>\r\n\r\n",
+                "1234",
+                "local.machine.example",
+            ),
+            (b" (c) <a.b@[192.0.2.1]> (c)\r\n\r\n", "a.b", "[192.0.2.1]"),
+            // Obsolete forms
+            (b"<\"a b\"@example.com>\r\n\r\n", "a b", "example.com"),
+            (b"<a . b@ example . com>\r\n\r\n", "a.b", "example.com"),
+        ];
+
+        for (test, id_left, id_right) in tests {
+            let (rem, got) = msg_id(test).unwrap();
+            assert_eq!(rem, b"\r\n\r\n");
+            assert_eq!(got.id_left(), *id_left);
+            assert_eq!(got.id_right(), *id_right);
+        }
+
+        assert!(msg_id(b"1234@example.com\r\n").is_err());
+        assert!(msg_id(b"<1234>\r\n").is_err());
+        // Control characters can not be written back.
+        assert!(msg_id(b"<\"a\\\x01b\"@example.com>\r\n\r\n").is_err());
+
+        // Phrases are skipped in the obsolete form.
+        let (rem, got) =
+            obs_references(b"References : <1@a.example> Some Phrase <2@b.example>\r\n\r\n")
+                .unwrap();
+        assert_eq!(rem, b"\r\n");
+        assert_eq!(
+            got,
+            vec![
+                MessageId::new("1", "a.example").unwrap(),
+                MessageId::new("2", "b.example").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_message_id() {
+        let first = MessageId::generate("example.com").unwrap();
+        let second = MessageId::generate("example.com").unwrap();
+        assert_ne!(first, second);
+
+        let mut out = Vec::new();
+        first.serialize(&mut out).unwrap();
+        out.extend_from_slice(b"\r\n\r\n");
+        let (_, got) = msg_id(&out).unwrap();
+        assert_eq!(got, first);
+
+        assert!(MessageId::generate("not a domain").is_err());
+        assert!(MessageId::generate("[192.0.2.1]").is_ok());
+    }
+
+    #[test]
+    fn test_fields() {
+        let header = b"\
+From: John Doe <jdoe@machine.example>\r\n\
+To: Mary Smith <mary@example.net>,\r\n\
+\x20Mary Jones <jones@example.net>\r\n\
+Subject: Saying Hello \r\n\
+Date: Fri, 21 Nov 1997 09:55:06 -0600\r\n\
+Message-ID: <1234@local.machine.example>\r\n\
+References: <1234@local.machine.example>\r\n\
+\t(comment) <3456@example.net>\r\n\
+X-Mailer: Foo\r\n\
+\r\n\
+This is a message just to say hello.\r\n";
+
+        let (rem, got) = fields(header).unwrap();
+        assert_eq!(rem, b"\r\nThis is a message just to say hello.\r\n");
+
+        let values: Vec<_> = got.iter().map(|field| field.value.clone()).collect();
+        assert_eq!(
+            values,
+            vec![
+                FieldValue::From(vec![mailbox(Some("John Doe"), "jdoe", "machine.example")]),
+                FieldValue::To(vec![
+                    Address::Mailbox(mailbox(Some("Mary Smith"), "mary", "example.net")),
+                    Address::Mailbox(mailbox(Some("Mary Jones"), "jones", "example.net")),
+                ]),
+                FieldValue::Subject("Saying Hello".into()),
+                FieldValue::Date(DateTime {
+                    day_of_week: Some(DayOfWeek::Friday),
+                    day: 21,
+                    month: Month::November,
+                    year: 1997,
+                    hour: 9,
+                    minute: 55,
+                    second: Some(6),
+                    zone: -360,
+                }),
+                FieldValue::MessageId(MessageId::new("1234", "local.machine.example").unwrap()),
+                FieldValue::References(vec![
+                    MessageId::new("1234", "local.machine.example").unwrap(),
+                    MessageId::new("3456", "example.net").unwrap(),
+                ]),
+                FieldValue::Unstructured("Foo".into()),
+            ]
+        );
+
+        assert_eq!(got[1].name, "To");
+        assert_eq!(
+            got[1].raw,
+            b"To: Mary Smith <mary@example.net>,\r\n Mary Jones <jones@example.net>\r\n"
+        );
+    }
+
+    #[test]
+    fn test_fields_encoded_words() {
+        let header = b"\
+From: =?ISO-8859-1?Q?Andr=E9?= Pirard <PIRARD@vm1.ulg.ac.be>\r\n\
+To: =?US-ASCII?Q?Keith?= =?US-ASCII?Q?_Moore?= <moore@cs.utk.edu>,\r\n\
+\x20\"=?ISO-8859-1?Q?not_decoded?=\" <x@example.com>\r\n\
+Subject: =?ISO-8859-1?B?SWYgeW91IGNhbiByZWFkIHRoaXMgeW8=?=\r\n\
+\x20=?ISO-8859-2?B?dSB1bmRlcnN0YW5kIHRoZSBleGFtcGxlLg==?=\r\n\
+\r\n";
+
+        let (_, got) = fields(header).unwrap();
+        let values: Vec<_> = got.into_iter().map(|field| field.value).collect();
+        assert_eq!(
+            values,
+            vec![
+                FieldValue::From(vec![mailbox(
+                    Some("André Pirard"),
+                    "PIRARD",
+                    "vm1.ulg.ac.be"
+                )]),
+                FieldValue::To(vec![
+                    Address::Mailbox(mailbox(Some("Keith Moore"), "moore", "cs.utk.edu")),
+                    Address::Mailbox(mailbox(
+                        Some("=?ISO-8859-1?Q?not_decoded?="),
+                        "x",
+                        "example.com"
+                    )),
+                ]),
+                FieldValue::Subject("If you can read this you understand the example.".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_raw_field_roundtrip() {
+        let tests = [
+            ("Subject", "Hello\t World".to_owned()),
+            ("X-Long", "word ".repeat(100).trim().to_owned()),
+            ("X-Tabs", "a\t\t\tb ".repeat(40).trim().to_owned()),
+            (
+                "To",
+                "Mary Smith <mary@example.net>, ".repeat(10) + "jdoe@example.org",
+            ),
+        ];
+
+        for (name, value) in tests {
+            let field = RawField::new(name, &value).unwrap();
+            let mut out = Vec::new();
+            field.serialize(&mut out).unwrap();
+            assert!(out.split(|b| *b == b'\n').all(|line| line.len() <= 80));
+            out.extend_from_slice(b"\r\n");
+
+            let (rem, got) = fields(&out).unwrap();
+            assert_eq!(rem, b"\r\n");
+            assert_eq!(got.len(), 1);
+            assert_eq!(got[0].name, name);
+            assert_eq!(got[0].raw, &out[..out.len() - 2]);
+            match &got[0].value {
+                FieldValue::Subject(got) | FieldValue::Unstructured(got) => assert_eq!(got, &value),
+                FieldValue::To(got) => assert_eq!(got.len(), 11),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_raw_field_roundtrip_non_ascii() {
+        // Non-ASCII text can not be written as is ...
+        assert!(RawField::new("Subject", "Grüße").is_err());
+
+        // ... but as encoded-words.
+        let value = encode("Grüße aus Köln", Encoding::Q).join(" ");
+        let field = RawField::new("Subject", value).unwrap();
+        let mut out = Vec::new();
+        field.serialize(&mut out).unwrap();
+        out.extend_from_slice(b"\r\n");
+
+        let (rem, got) = fields(&out).unwrap();
+        assert_eq!(rem, b"\r\n");
+        assert_eq!(got[0].value, FieldValue::Subject("Grüße aus Köln".into()));
+    }
+
+    #[test]
+    fn test_obs_fields() {
+        let header = b"Subject : Hello\x01\r\nX-Foo\t: bar\r\nDate: 21 May 98 05:33 EST\r\n\r\n";
+
+        let (rem, got) = obs_fields(header).unwrap();
+        assert_eq!(rem, b"\r\n");
+        assert_eq!(got[0].name, "Subject");
+        assert_eq!(got[0].value, FieldValue::Subject("Hello\x01".into()));
+        assert_eq!(got[1].value, FieldValue::Unstructured("bar".into()));
+        assert!(matches!(
+            got[2].value,
+            FieldValue::Date(DateTime {
+                year: 1998,
+                zone: -300,
+                ..
+            })
+        ));
+
+        // Obsolete syntax is not accepted in strict mode.
+        assert!(fields(header).is_err());
+        assert!(fields(b"Subject: Hello\x01\r\n\r\n").is_err());
+
+        // Malformed unknown fields are not skipped silently.
+        for parser in [fields, obs_fields] {
+            assert!(parser(b"X-Foo: bar\r\nX-Bar bar\r\n\r\n").is_err());
+            assert!(parser(b"X-Foo: bar\r\n: bar\r\n\r\n").is_err());
+        }
+
+        // Malformed values of known fields are rejected in both modes.
+        for parser in [fields, obs_fields] {
+            assert!(matches!(
+                parser(b"To: Mary Smith\r\n\r\n"),
+                Err(nom::Err::Failure(_))
+            ));
+            assert!(matches!(
+                parser(b"Date: yesterday\r\n\r\n"),
+                Err(nom::Err::Failure(_))
+            ));
+            assert!(matches!(
+                parser(b"Message-ID: 1234\r\n\r\n"),
+                Err(nom::Err::Failure(_))
+            ));
+        }
+    }
+}
@@ -5,16 +5,18 @@ use nom::{
     branch::alt,
     bytes::streaming::{tag, take_while, take_while1, take_while_m_n},
     character::streaming::digit1,
-    combinator::{map, map_res, opt, recognize},
-    multi::{many0, separated_list1},
+    combinator::{map, map_res, opt, recognize, verify},
+    multi::{many0, many1, separated_list1},
     sequence::{delimited, tuple},
     IResult,
 };
 
-use crate::{parse::imf::atom::is_atext, utils::unescape_quoted, AtomOrQuoted};
+use crate::{parse::imf::atom::is_atext, utils::unescape_quoted, AtomOrQuoted, Domain};
 
 pub mod address;
+pub mod auth_results;
 pub mod command;
+pub mod encoded_word;
 pub mod imf;
 pub mod response;
 pub mod trace;
@@ -90,35 +92,120 @@ pub fn is_qtext_smtp(byte: u8) -> bool {
 ///
 /// quoted-pairSMTP = %d92 %d32-126
 ///
-/// FIXME: How should e.g. "\a" be interpreted?
+/// The quoted-pair is interpreted as the character following the backslash, e.g., "\a" as "a".
 pub fn quoted_pair_smtp(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    //fn is_value(byte: u8) -> bool {
-    //    matches!(byte, 32..=126)
-    //}
-
-    // FIXME: Only allow "\\" and "\"" for now ...
-    fn is_value(byte: u8) -> bool {
-        byte == b'\\' || byte == b'\"'
-    }
-
-    let parser = tuple((tag("\\"), take_while_m_n(1, 1, is_value)));
+    let parser = tuple((
+        tag("\\"),
+        take_while_m_n(1, 1, |byte| matches!(byte, 32..=126)),
+    ));
 
     let (remaining, parsed) = recognize(parser)(input)?;
 
     Ok((remaining, parsed))
 }
 
+// ----- SMTPUTF8 (RFC 6531) -----
+
+/// String = Atom / Quoted-string
+///
+/// Use this parser instead of [`string`] when SMTPUTF8 was negotiated.
+pub fn string_utf8(input: &[u8]) -> IResult<&[u8], AtomOrQuoted> {
+    alt((
+        map(atom_utf8, |atom| AtomOrQuoted::Atom(atom.into())),
+        map(quoted_string_utf8, |quoted| {
+            AtomOrQuoted::Quoted(quoted.into())
+        }),
+    ))(input)
+}
+
+/// Atom = 1*atext
+///
+/// atext =/ UTF8-non-ascii
+pub fn atom_utf8(input: &[u8]) -> IResult<&[u8], &str> {
+    map_res(
+        recognize(many1(alt((take_while1(is_atext), utf8_non_ascii)))),
+        std::str::from_utf8,
+    )(input)
+}
+
+/// Quoted-string = DQUOTE *QcontentSMTP DQUOTE
+///
+/// qtextSMTP =/ UTF8-non-ascii
+pub fn quoted_string_utf8(input: &[u8]) -> IResult<&[u8], Cow<'_, str>> {
+    map(
+        delimited(
+            DQUOTE,
+            map_res(recognize(many0(q_content_smtp_utf8)), std::str::from_utf8),
+            DQUOTE,
+        ),
+        unescape_quoted,
+    )(input)
+}
+
+/// QcontentSMTP = qtextSMTP / quoted-pairSMTP
+///
+/// qtextSMTP =/ UTF8-non-ascii
+pub fn q_content_smtp_utf8(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((
+        take_while_m_n(1, 1, is_qtext_smtp),
+        utf8_non_ascii,
+        quoted_pair_smtp,
+    ))(input)
+}
+
+/// UTF8-non-ascii = UTF8-2 / UTF8-3 / UTF8-4  ; RFC 6532
+///
+/// Note: C1 control characters, i.e., U+0080 to U+009F, are rejected.
+pub fn utf8_non_ascii(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let parser = tuple((
+        take_while_m_n(1, 1, |byte| matches!(byte, 0xC2..=0xF4)),
+        take_while_m_n(1, 3, |byte| matches!(byte, 0x80..=0xBF)),
+    ));
+
+    verify(
+        recognize(parser),
+        |parsed: &[u8]| match std::str::from_utf8(parsed) {
+            Ok(character) => !character.chars().any(char::is_control),
+            Err(_) => false,
+        },
+    )(input)
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// Domain = sub-domain *("." sub-domain)
-pub fn domain(input: &[u8]) -> IResult<&[u8], &str> {
+///
+/// Note: Labels longer than 63 octets and domains longer than 255 octets are rejected.
+pub fn domain(input: &[u8]) -> IResult<&[u8], Domain> {
     let parser = separated_list1(tag(b"."), sub_domain);
 
-    let (remaining, parsed) = map_res(recognize(parser), std::str::from_utf8)(input)?;
+    let (remaining, parsed) =
+        map_res(map_res(recognize(parser), std::str::from_utf8), Domain::new)(input)?;
+
+    Ok((remaining, parsed))
+}
+
+/// Domain = sub-domain *("." sub-domain)
+///
+/// sub-domain =/ U-label  ; RFC 6531
+///
+/// Use this parser instead of [`domain`] when SMTPUTF8 was negotiated.
+pub fn domain_utf8(input: &[u8]) -> IResult<&[u8], Domain> {
+    let parser = separated_list1(tag(b"."), sub_domain_utf8);
+
+    let (remaining, parsed) =
+        map_res(map_res(recognize(parser), std::str::from_utf8), Domain::new)(input)?;
 
     Ok((remaining, parsed))
 }
 
+/// sub-domain =/ U-label
+///
+/// Note: The labels are validated by [`Domain::new`], i.e., according to IDNA 2008.
+pub fn sub_domain_utf8(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|byte| is_let_dig(byte) || byte == b'-' || !byte.is_ascii())(input)
+}
+
 /// sub-domain = Let-dig [Ldh-str]
 pub fn sub_domain(input: &[u8]) -> IResult<&[u8], &[u8]> {
     let parser = tuple((take_while_m_n(1, 1, is_let_dig), opt(ldh_str)));
@@ -138,7 +225,10 @@ pub fn ldh_str(input: &[u8]) -> IResult<&[u8], &[u8]> {
     let parser = many0(alt((
         take_while_m_n(1, 1, is_ALPHA),
         take_while_m_n(1, 1, is_DIGIT),
-        recognize(tuple((tag(b"-"), take_while_m_n(1, 1, is_let_dig)))),
+        recognize(tuple((
+            take_while1(|byte| byte == b'-'),
+            take_while_m_n(1, 1, is_let_dig),
+        ))),
     )));
 
     let (remaining, parsed) = recognize(parser)(input)?;
@@ -150,7 +240,10 @@ pub fn ldh_str(input: &[u8]) -> IResult<&[u8], &[u8]> {
 
 #[cfg(test)]
 pub mod test {
-    use super::sub_domain;
+    use super::{
+        atom_utf8, domain, domain_utf8, quoted_string, quoted_string_utf8, string, sub_domain,
+    };
+    use crate::AtomOrQuoted;
 
     #[test]
     fn test_subdomain() {
@@ -158,4 +251,91 @@ pub mod test {
         assert_eq!(parsed, b"example");
         assert_eq!(rem, b"???");
     }
+
+    #[test]
+    fn test_quoted_string() {
+        let tests: &[(&[u8], &str)] = &[
+            (br#""""#, ""),
+            (br#""john smith""#, "john smith"),
+            (br#""john\ smith""#, "john smith"),
+            (br#""\a\~\ ""#, "a~ "),
+            (br#""\\\"""#, "\\\""),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, got) = quoted_string(test).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(got, *expected);
+        }
+
+        // Only %d32-126 may follow a backslash.
+        assert!(quoted_string(b"\"\\\x7f\"").is_err());
+        assert!(quoted_string(b"\"\\\t\"").is_err());
+
+        let (_, got) = string(br#""a\b" "#).unwrap();
+        assert_eq!(got, AtomOrQuoted::Quoted("ab".into()));
+    }
+
+    #[test]
+    fn test_utf8_non_ascii() {
+        let (rem, got) = atom_utf8("jöhn😀 ".as_bytes()).unwrap();
+        assert_eq!(got, "jöhn😀");
+        assert_eq!(rem, b" ");
+
+        let (rem, got) = quoted_string_utf8("\"jöhn döe\" ".as_bytes()).unwrap();
+        assert_eq!(got, "jöhn döe");
+        assert_eq!(rem, b" ");
+
+        // C1 control characters and malformed UTF-8
+        let tests: &[&[u8]] = &[
+            "a\u{80}b ".as_bytes(),
+            "a\u{85}b ".as_bytes(),
+            "a\u{9f}b ".as_bytes(),
+            b"a\xffb ",
+            b"a\xc3\xc3b ",
+            b"a\xc0\x80b ",
+        ];
+
+        for test in tests {
+            let (rem, _) = atom_utf8(test).unwrap();
+            assert_ne!(rem, b" ", "{:?}", test);
+            assert!(quoted_string_utf8(&[b"\"", *test, b"\""].concat()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_domain() {
+        let (rem, parsed) = domain(b"Mail.Example.COM ").unwrap();
+        assert_eq!(parsed.as_str(), "Mail.Example.COM");
+        assert_eq!(rem, b" ");
+
+        let (rem, parsed) = domain(b"xn--bcher-kva.example ").unwrap();
+        assert_eq!(parsed.as_str(), "xn--bcher-kva.example");
+        assert_eq!(rem, b" ");
+
+        let label = "a".repeat(64);
+        assert!(domain(format!("{}.com ", label).as_bytes()).is_err());
+
+        let label = "a".repeat(63);
+        let long = [label.as_str(); 4].join(".");
+        assert!(domain(format!("{} ", long).as_bytes()).is_ok());
+        assert!(domain(format!("{}.a ", long).as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_domain_utf8() {
+        let (rem, _) = domain("bücher.example ".as_bytes()).unwrap();
+        assert_eq!(rem, "ücher.example ".as_bytes());
+
+        let (rem, parsed) = domain_utf8("bücher.example>".as_bytes()).unwrap();
+        assert_eq!(parsed.as_str(), "bücher.example");
+        assert_eq!(parsed.to_ascii().as_str(), "xn--bcher-kva.example");
+        assert_eq!(rem, b">");
+
+        let (_, a_label) = domain_utf8(b"xn--bcher-kva.example>").unwrap();
+        assert_eq!(a_label, parsed);
+
+        assert!(domain_utf8("-bücher.example>".as_bytes()).is_err());
+        assert!(domain_utf8(b"\xff.example>").is_err());
+    }
 }
@@ -12,7 +12,7 @@ use nom::{
 
 use crate::{
     parse::{address::address_literal, domain, number},
-    AuthMechanism, Capability, ReplyCode, Response, TextString,
+    AuthMechanism, Capability, DomainOrAddress, ReplyCode, Response, TextString,
 };
 
 /// Greeting = ( "220 " (Domain / address-literal) [ SP textstring ] CRLF ) /
@@ -24,12 +24,15 @@ pub fn greeting(input: &[u8]) -> IResult<&[u8], Response> {
         map(
             tuple((
                 tag(b"220 "),
-                alt((domain, address_literal)),
+                alt((
+                    map(domain, DomainOrAddress::Domain),
+                    map(address_literal, DomainOrAddress::Address),
+                )),
                 opt(preceded(SP, textstring)),
                 CRLF,
             )),
             |(_, domain, maybe_text, _)| Response::Greeting {
-                domain: domain.to_owned(),
+                domain,
                 text: maybe_text
                     .map(|str| str.to_string())
                     .unwrap_or_else(|| "".to_string()),
@@ -38,7 +41,10 @@ pub fn greeting(input: &[u8]) -> IResult<&[u8], Response> {
         map(
             tuple((
                 tag(b"220-"),
-                alt((domain, address_literal)),
+                alt((
+                    map(domain, DomainOrAddress::Domain),
+                    map(address_literal, DomainOrAddress::Address),
+                )),
                 opt(preceded(SP, textstring)),
                 CRLF,
                 many0(delimited(tag(b"220-"), opt(textstring), CRLF)),
@@ -47,7 +53,7 @@ pub fn greeting(input: &[u8]) -> IResult<&[u8], Response> {
                 CRLF,
             )),
             |(_, domain, maybe_text, _, more_text, _, moar_text, _)| Response::Greeting {
-                domain: domain.to_owned(),
+                domain,
                 text: {
                     let mut res = maybe_text
                         .map(|str| format!("{}\n", str))
@@ -155,7 +161,7 @@ pub fn ehlo_ok_rsp(input: &[u8]) -> IResult<&[u8], Response> {
         map(
             tuple((tag(b"250 "), domain, opt(preceded(SP, ehlo_greet)), CRLF)),
             |(_, domain, maybe_ehlo, _)| Response::Ehlo {
-                domain: domain.to_owned(),
+                domain,
                 greet: maybe_ehlo.map(|ehlo| ehlo.to_owned()),
                 capabilities: Vec::new(),
             },
@@ -172,7 +178,7 @@ pub fn ehlo_ok_rsp(input: &[u8]) -> IResult<&[u8], Response> {
                 CRLF,
             )),
             |(_, domain, maybe_ehlo, _, mut lines, _, line, _)| Response::Ehlo {
-                domain: domain.to_owned(),
+                domain,
                 greet: maybe_ehlo.map(|ehlo| ehlo.to_owned()),
                 capabilities: {
                     lines.push(line);
@@ -299,7 +305,7 @@ pub fn auth_mechanism(input: &[u8]) -> IResult<&[u8], AuthMechanism> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::AuthMechanism;
+    use crate::{AuthMechanism, Domain};
 
     #[test]
     fn test_greeting() {
@@ -312,7 +318,7 @@ mod test {
         assert_eq!(
             out,
             Response::Greeting {
-                domain: "example.org".into(),
+                domain: Domain::new("example.org").unwrap().into(),
                 text: "ESMTP Fake 4.93 #2 Thu, 16 Jul 2020 07:30:16 -0400\n\
 We do not authorize the use of this system to transport unsolicited,\n\
 and/or bulk e-mail."
@@ -336,7 +342,7 @@ and/or bulk e-mail."
         assert_eq!(
             out,
             Response::Ehlo {
-                domain: "example.org".into(),
+                domain: Domain::new("example.org").unwrap(),
                 greet: Some("hello".into()),
                 capabilities: vec![
                     Capability::Auth(vec![
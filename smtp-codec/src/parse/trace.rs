@@ -3,41 +3,133 @@ use abnf_core::streaming::CRLF;
 use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
-    combinator::{map_res, opt, recognize},
+    combinator::{map, map_res, opt},
     multi::many1,
-    sequence::tuple,
+    sequence::{delimited, preceded, tuple},
     IResult,
 };
 
-use crate::parse::{
-    address::address_literal,
-    atom,
-    command::{mailbox, path, reverse_path},
-    domain,
-    imf::{
-        datetime::date_time,
-        folding_ws_and_comment::{cfws, fws},
-        identification::msg_id,
+use crate::{
+    imf::{DateTime, MessageId},
+    parse::{
+        address::address_literal,
+        atom,
+        command::{mailbox, path, reverse_path},
+        domain,
+        imf::{
+            addr_spec::{domain_content, local_part_content},
+            datetime::{date_time, date_time_unchecked, obs_date_time_unchecked},
+            folding_ws_and_comment::{cfws, fws},
+        },
+        string,
+        utils::{has_field_name, header_fields},
     },
-    string,
+    trace::{
+        AdditionalClause, ExtendedDomain, Hop, MalformedStamp, Protocol, Received, ReceivedId,
+        ReturnPath, StampError, TcpInfo,
+    },
+    Path,
 };
 
 /// Return-path-line = "Return-Path:" FWS Reverse-path <CRLF>
-pub fn return_path_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((tag_no_case(b"Return-Path:"), fws, reverse_path, CRLF));
+pub fn return_path_line(input: &[u8]) -> IResult<&[u8], ReturnPath> {
+    let mut parser = tuple((tag_no_case(b"Return-Path:"), fws, reverse_path, CRLF));
+
+    let (remaining, (_, _, reverse_path, _)) = parser(input)?;
+
+    Ok((remaining, ReturnPath::from(&reverse_path)))
+}
+
+/// Count the "Delivered-To:" header fields for `recipient` in a header section.
+///
+/// A delivery agent should refuse to deliver a message that already carries a Delivered-To
+/// header field for the recipient, because the message is looping. Recipients are compared
+/// case-insensitively and may be enclosed in angle brackets.
+pub fn count_delivered_to(header: &[u8], recipient: &str) -> usize {
+    header_fields(header)
+        .into_iter()
+        .filter(|field| has_field_name(field, b"Delivered-To"))
+        .filter(|field| {
+            let value =
+                String::from_utf8_lossy(&field[b"Delivered-To:".len()..]).replace("\r\n", "");
+            let value = value.trim();
+            let value = value
+                .strip_prefix('<')
+                .and_then(|value| value.strip_suffix('>'))
+                .unwrap_or(value);
+
+            value.eq_ignore_ascii_case(recipient)
+        })
+        .count()
+}
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+/// Extract the "Received:" chain from a header section.
+///
+/// Hops are returned in header order, i.e., the most recent hop comes first. A stamp that
+/// can not be parsed is reported as [`MalformedStamp`] without affecting the other hops.
+///
+/// Note: Lines must be terminated by CRLF.
+pub fn received_chain(header: &[u8]) -> Vec<Result<Hop, MalformedStamp>> {
+    let mut hops: Vec<Result<Hop, MalformedStamp>> = header_fields(header)
+        .into_iter()
+        .filter(|field| has_field_name(field, b"Received"))
+        .map(|field| {
+            // The empty line makes sure that the streaming parser does not wait for more
+            // folded lines.
+            let input = [field, b"\r\n"].concat();
+
+            match time_stamp_line(&input) {
+                Ok((b"\r\n", received)) => Ok(Hop::from(received)),
+                _ => Err(MalformedStamp {
+                    raw: field.to_vec(),
+                    error: diagnose(&input),
+                }),
+            }
+        })
+        .collect();
+
+    for index in 1..hops.len() {
+        let (newer, older) = hops.split_at_mut(index);
+
+        if let (Ok(newer), Ok(older)) = (&mut newer[index - 1], &older[0]) {
+            newer.delay =
+                Some(newer.received.date_time.timestamp() - older.received.date_time.timestamp());
+        }
+    }
+
+    hops
+}
 
-    Ok((remaining, parsed))
+/// Find out why a time-stamp-line could not be parsed.
+fn diagnose(input: &[u8]) -> StampError {
+    if let Ok((b"\r\n", received)) = time_stamp_line_with(input, date_time_unchecked) {
+        if let Err(error) = received.date_time.validate() {
+            return StampError::InvalidDateTime(error);
+        }
+    }
+
+    match time_stamp_line_with(input, obs_date_time_unchecked) {
+        Ok((b"\r\n", _)) => StampError::ObsoleteDateTime,
+        _ => StampError::Syntax,
+    }
 }
 
 /// Time-stamp-line = "Received:" FWS Stamp <CRLF>
-pub fn time_stamp_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((tag_no_case(b"Received:"), fws, stamp, CRLF));
+pub fn time_stamp_line(input: &[u8]) -> IResult<&[u8], Received> {
+    time_stamp_line_with(input, date_time)
+}
+
+fn time_stamp_line_with(input: &[u8], date_time: DateTimeParser) -> IResult<&[u8], Received> {
+    let mut parser = tuple((
+        tag_no_case(b"Received:"),
+        fws,
+        |input| stamp_with(input, date_time),
+        CRLF,
+    ));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let (remaining, (_, _, received, _)) = parser(input)?;
 
-    Ok((remaining, parsed))
+    Ok((remaining, received))
 }
 
 /// Stamp = From-domain By-domain Opt-info [cfws] ";" FWS date-time
@@ -45,8 +137,14 @@ pub fn time_stamp_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
 /// Caution: Where "date-time" is as defined in RFC 5322 [4]
 ///          but the "obs-" forms, especially two-digit
 ///          years, are prohibited in SMTP and MUST NOT be used.
-pub fn stamp(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((
+pub fn stamp(input: &[u8]) -> IResult<&[u8], Received> {
+    stamp_with(input, date_time)
+}
+
+type DateTimeParser = fn(&[u8]) -> IResult<&[u8], DateTime>;
+
+fn stamp_with(input: &[u8], date_time: DateTimeParser) -> IResult<&[u8], Received> {
+    let mut parser = tuple((
         from_domain,
         by_domain,
         opt_info,
@@ -56,67 +154,87 @@ pub fn stamp(input: &[u8]) -> IResult<&[u8], &[u8]> {
         date_time,
     ));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+    let (remaining, (from, by, (via, with, id, for_path, additional_clauses), _, _, _, date_time)) =
+        parser(input)?;
+
+    Ok((
+        remaining,
+        Received {
+            from,
+            by,
+            via: via.map(String::from),
+            with,
+            id,
+            for_path,
+            additional_clauses,
+            date_time,
+        },
+    ))
 }
 
 /// From-domain = "FROM" FWS Extended-Domain
-pub fn from_domain(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((tag_no_case(b"FROM"), fws, extended_domain));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn from_domain(input: &[u8]) -> IResult<&[u8], ExtendedDomain> {
+    preceded(tuple((tag_no_case(b"FROM"), fws)), extended_domain)(input)
 }
 
 /// By-domain = CFWS "BY" FWS Extended-Domain
-pub fn by_domain(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((cfws, tag_no_case(b"BY"), fws, extended_domain));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn by_domain(input: &[u8]) -> IResult<&[u8], ExtendedDomain> {
+    preceded(tuple((cfws, tag_no_case(b"BY"), fws)), extended_domain)(input)
 }
 
 /// Extended-Domain = Domain /
 ///                   ( Domain FWS "(" TCP-info ")" ) /
 ///                   ( address-literal FWS "(" TCP-info ")" )
-pub fn extended_domain(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = alt((
-        recognize(domain),
-        recognize(tuple((domain, fws, tag(b"("), tcp_info, tag(b")")))),
-        recognize(tuple((
-            address_literal,
-            fws,
-            tag(b"("),
-            tcp_info,
-            tag(b")"),
-        ))),
-    ));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn extended_domain(input: &[u8]) -> IResult<&[u8], ExtendedDomain> {
+    // Note: The longer alternatives must be tried first.
+    alt((
+        map(
+            tuple((domain, fws, delimited(tag(b"("), tcp_info, tag(b")")))),
+            |(domain, _, tcp_info)| ExtendedDomain::DomainWithTcpInfo { domain, tcp_info },
+        ),
+        map(
+            tuple((
+                address_literal,
+                fws,
+                delimited(tag(b"("), tcp_info, tag(b")")),
+            )),
+            |(address, _, tcp_info)| ExtendedDomain::AddressWithTcpInfo { address, tcp_info },
+        ),
+        map(domain, ExtendedDomain::Domain),
+    ))(input)
 }
 
 /// Information derived by server from TCP connection not client EHLO.
 ///
 /// TCP-info = address-literal / ( Domain FWS address-literal )
-pub fn tcp_info(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = alt((
-        recognize(address_literal),
-        recognize(tuple((domain, fws, address_literal))),
-    ));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn tcp_info(input: &[u8]) -> IResult<&[u8], TcpInfo> {
+    alt((
+        map(address_literal, |address| TcpInfo {
+            reverse_name: None,
+            address,
+        }),
+        map(
+            tuple((domain, fws, address_literal)),
+            |(reverse_name, _, address)| TcpInfo {
+                reverse_name: Some(reverse_name),
+                address,
+            },
+        ),
+    ))(input)
 }
 
+/// Via, With, ID, For, and Additional-Registered-Clauses
+pub type OptInfo<'a> = (
+    Option<&'a str>,
+    Option<Protocol>,
+    Option<ReceivedId>,
+    Option<Path>,
+    Vec<AdditionalClause>,
+);
+
 /// Opt-info = [Via] [With] [ID] [For] [Additional-Registered-Clauses]
-pub fn opt_info(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((
+pub fn opt_info(input: &[u8]) -> IResult<&[u8], OptInfo<'_>> {
+    let mut parser = tuple((
         opt(via),
         opt(with),
         opt(id),
@@ -124,68 +242,73 @@ pub fn opt_info(input: &[u8]) -> IResult<&[u8], &[u8]> {
         opt(additional_registered_clauses),
     ));
 
-    let (remaining, parsed) = recognize(parser)(input)?;
+    let (remaining, (via, with, id, for_path, maybe_clauses)) = parser(input)?;
 
-    Ok((remaining, parsed))
+    Ok((
+        remaining,
+        (via, with, id, for_path, maybe_clauses.unwrap_or_default()),
+    ))
 }
 
 /// Via = CFWS "VIA" FWS Link
-pub fn via(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((cfws, tag_no_case(b"VIA"), fws, link));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn via(input: &[u8]) -> IResult<&[u8], &str> {
+    preceded(tuple((cfws, tag_no_case(b"VIA"), fws)), link)(input)
 }
 
 /// With = CFWS "WITH" FWS Protocol
-pub fn with(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((cfws, tag_no_case(b"WITH"), fws, protocol));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn with(input: &[u8]) -> IResult<&[u8], Protocol> {
+    preceded(tuple((cfws, tag_no_case(b"WITH"), fws)), protocol)(input)
 }
 
 /// ID = CFWS "ID" FWS ( Atom / msg-id )
 ///       ; msg-id is defined in RFC 5322 [4]
-pub fn id(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((
-        cfws,
-        tag_no_case(b"ID"),
-        fws,
-        recognize(alt((recognize(atom), msg_id))),
-    ));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+///
+/// Note: The optional CFWS around msg-id is not consumed here. Otherwise, the trailing CFWS
+///       would swallow the CFWS required by the next clause.
+pub fn id(input: &[u8]) -> IResult<&[u8], ReceivedId> {
+    preceded(
+        tuple((cfws, tag_no_case(b"ID"), fws)),
+        alt((
+            map(atom, |atom| ReceivedId::Atom(atom.to_owned())),
+            map(
+                map_res(
+                    tuple((
+                        tag(b"<"),
+                        local_part_content,
+                        tag(b"@"),
+                        domain_content,
+                        tag(b">"),
+                    )),
+                    |(_, id_left, _, id_right, _)| MessageId::new_obs(id_left, id_right),
+                ),
+                ReceivedId::MsgId,
+            ),
+        )),
+    )(input)
 }
 
 /// For = CFWS "FOR" FWS ( Path / Mailbox )
-pub fn r#for(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = tuple((
-        cfws,
-        tag_no_case(b"FOR"),
-        fws,
-        alt((recognize(path), mailbox)),
-    ));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+///
+/// A Mailbox is returned as a Path without source route.
+pub fn r#for(input: &[u8]) -> IResult<&[u8], Path> {
+    preceded(
+        tuple((cfws, tag_no_case(b"FOR"), fws)),
+        alt((path, map(mailbox, Path::from))),
+    )(input)
 }
 
 /// Additional standard clauses may be added in this location by future standards and registration with
 /// IANA.  SMTP servers SHOULD NOT use unregistered names.  See Section 8.
 ///
 /// Additional-Registered-Clauses = CFWS Atom FWS String
-pub fn additional_registered_clauses(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let parser = many1(tuple((cfws, atom, fws, string)));
-
-    let (remaining, parsed) = recognize(parser)(input)?;
-
-    Ok((remaining, parsed))
+pub fn additional_registered_clauses(input: &[u8]) -> IResult<&[u8], Vec<AdditionalClause>> {
+    many1(map(
+        tuple((cfws, atom, fws, string)),
+        |(_, name, _, value)| AdditionalClause {
+            name: name.to_owned(),
+            value,
+        },
+    ))(input)
 }
 
 /// Link = "TCP" / Addtl-Link
@@ -203,12 +326,10 @@ pub fn addtl_link(input: &[u8]) -> IResult<&[u8], &str> {
 }
 
 /// Protocol = "ESMTP" / "SMTP" / Attdl-Protocol
-pub fn protocol(input: &[u8]) -> IResult<&[u8], &str> {
-    alt((
-        map_res(tag_no_case(b"ESMTP"), std::str::from_utf8),
-        map_res(tag_no_case(b"SMTP"), std::str::from_utf8),
-        attdl_protocol,
-    ))(input)
+///
+/// Note: "ESMTP" and "SMTP" are atoms, too. Matching them as tags would split, e.g., "ESMTPS".
+pub fn protocol(input: &[u8]) -> IResult<&[u8], Protocol> {
+    map(attdl_protocol, Protocol::from)(input)
 }
 
 /// Additional standard names for protocols are registered with the Internet Assigned Numbers
@@ -219,3 +340,547 @@ pub fn protocol(input: &[u8]) -> IResult<&[u8], &str> {
 pub fn attdl_protocol(input: &[u8]) -> IResult<&[u8], &str> {
     atom(input)
 }
+
+#[cfg(test)]
+mod test {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::{
+        count_delivered_to, extended_domain, protocol, received_chain, return_path_line,
+        time_stamp_line,
+    };
+    use crate::{
+        imf::{DateTime, DayOfWeek, InvalidDateTime, MessageId, Month},
+        trace::{
+            AdditionalClause, DeliveredTo, ExtendedDomain, MalformedStamp, Protocol, Received,
+            ReceivedId, ReturnPath, StampError, TcpInfo,
+        },
+        AtomOrQuoted, Command, Domain, DomainOrAddress, Mailbox, Path, ReversePath,
+    };
+
+    const DATE_TIME: DateTime = DateTime {
+        day_of_week: Some(DayOfWeek::Thursday),
+        day: 21,
+        month: Month::May,
+        year: 1998,
+        hour: 5,
+        minute: 33,
+        second: Some(29),
+        zone: -420,
+    };
+
+    #[test]
+    fn test_return_path_line() {
+        let jones = Mailbox::new("jones", Domain::new("foo.com").unwrap().into()).unwrap();
+
+        let tests: &[(&[u8], ReturnPath)] = &[
+            (b"Return-Path: <>\r\n", ReturnPath::Null),
+            (
+                b"Return-Path: <jones@foo.com>\r\n",
+                ReturnPath::Path(Path::from(jones.clone())),
+            ),
+            (
+                b"return-path:\r\n <@a.org,@b.org:jones@foo.com>\r\n",
+                ReturnPath::Path(Path {
+                    source_route: vec![
+                        Domain::new("a.org").unwrap(),
+                        Domain::new("b.org").unwrap(),
+                    ],
+                    mailbox: jones,
+                }),
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, return_path) = return_path_line(test).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(return_path, *expected);
+        }
+
+        for command in [
+            Command::Mail {
+                reverse_path: ReversePath::Null,
+                parameters: vec![],
+            },
+            Command::Mail {
+                reverse_path: ReversePath::Path(Path {
+                    source_route: vec![Domain::new("a.org").unwrap()],
+                    mailbox: Mailbox::new(
+                        "jones",
+                        DomainOrAddress::Domain(Domain::new("foo.com").unwrap()),
+                    )
+                    .unwrap(),
+                }),
+                parameters: vec![],
+            },
+        ] {
+            let return_path = ReturnPath::from_command(&command).unwrap();
+
+            let mut out = Vec::new();
+            return_path.serialize(&mut out).unwrap();
+
+            let (rem, got) = return_path_line(&out).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(got, return_path);
+        }
+
+        assert_eq!(ReturnPath::from_command(&Command::Data), None);
+    }
+
+    #[test]
+    fn test_delivered_to() {
+        let mut header = Vec::new();
+        DeliveredTo {
+            recipient: Mailbox::new("jones", Domain::new("foo.com").unwrap().into()).unwrap(),
+        }
+        .serialize(&mut header)
+        .unwrap();
+        assert_eq!(header, b"Delivered-To: jones@foo.com\r\n");
+
+        header.extend_from_slice(
+            b"Received: from bar.com by foo.com; Thu, 21 May 1998 05:33:29 -0700\r\n\
+delivered-to: <Jones@FOO.com>\r\n\
+Delivered-To: smith@foo.com\r\n\
+Delivered-To:\r\n jones@foo.com\r\n\
+\r\n\
+Delivered-To: jones@foo.com\r\n",
+        );
+
+        assert_eq!(count_delivered_to(&header, "jones@foo.com"), 3);
+        assert_eq!(count_delivered_to(&header, "smith@foo.com"), 1);
+        assert_eq!(count_delivered_to(&header, "miller@foo.com"), 0);
+
+        let mut out = Vec::new();
+        DeliveredTo {
+            recipient: Mailbox::new(
+                "Jones Smith",
+                DomainOrAddress::Address(Ipv4Addr::new(192, 0, 2, 1).into()),
+            )
+            .unwrap(),
+        }
+        .serialize(&mut out)
+        .unwrap();
+        assert_eq!(out, b"Delivered-To: \"Jones Smith\"@[192.0.2.1]\r\n");
+    }
+
+    #[test]
+    fn test_time_stamp_line() {
+        let tests: &[&[u8]] = &[
+            b"Received: from bar.com by foo.com ; Thu, 21 May 1998\r\n    05:33:29 -0700\r\n",
+            b"Received: from bar.com (bar.com [192.0.2.1]) by foo.com (Postfix) with ESMTP id ABC123\r\n\tfor <jones@foo.com>; Thu, 21 May 1998 05:33:29 -0700 (PDT)\r\n",
+            b"Received: from [192.0.2.1] ([192.0.2.1]) by foo.com via TCP with SMTP\r\n id <123@foo.com> for jones@foo.com; Thu, 21 May 1998 05:33:29 -0700\r\n",
+        ];
+
+        for test in tests {
+            let input = [*test, b"Date: ...\r\n"].concat();
+            let (rem, got) = time_stamp_line(&input).unwrap();
+            assert_eq!(rem, b"Date: ...\r\n");
+            assert_eq!(got.date_time, DATE_TIME);
+
+            let mut out = Vec::new();
+            got.serialize(&mut out).unwrap();
+            out.extend_from_slice(b"Date: ...\r\n");
+            let (rem, again) = time_stamp_line(&out).unwrap();
+            assert_eq!(rem, b"Date: ...\r\n");
+            assert_eq!(again, got);
+        }
+    }
+
+    #[test]
+    fn test_time_stamp_line_typed() {
+        let (rem, got) = time_stamp_line(b"Received: from bar.com (bar.com [192.0.2.1]) by foo.com (Postfix) with ESMTP id ABC123\r\n\tfor <jones@foo.com>; Thu, 21 May 1998 05:33:29 -0700 (PDT)\r\n\r\n").unwrap();
+        assert_eq!(rem, b"\r\n");
+        assert_eq!(
+            got,
+            Received {
+                from: ExtendedDomain::DomainWithTcpInfo {
+                    domain: Domain::new("bar.com").unwrap(),
+                    tcp_info: TcpInfo {
+                        reverse_name: Some(Domain::new("bar.com").unwrap()),
+                        address: Ipv4Addr::new(192, 0, 2, 1).into(),
+                    },
+                },
+                by: ExtendedDomain::Domain(Domain::new("foo.com").unwrap()),
+                via: None,
+                with: Some(Protocol::ESMTP),
+                id: Some(ReceivedId::Atom("ABC123".into())),
+                for_path: Some(Path::from(
+                    Mailbox::new("jones", Domain::new("foo.com").unwrap().into()).unwrap()
+                )),
+                additional_clauses: vec![],
+                date_time: DATE_TIME,
+            }
+        );
+
+        let (rem, got) = time_stamp_line(b"Received: from [192.0.2.1] ([192.0.2.1]) by foo.com via TCP with SMTP\r\n id <123@foo.com> for <@a.org:jones@foo.com>; Thu, 21 May 1998 05:33:29 -0700\r\n\r\n").unwrap();
+        assert_eq!(rem, b"\r\n");
+        assert_eq!(
+            got.id,
+            Some(ReceivedId::MsgId(MessageId::new("123", "foo.com").unwrap()))
+        );
+        assert_eq!(
+            got.for_path,
+            Some(Path {
+                source_route: vec![Domain::new("a.org").unwrap()],
+                mailbox: Mailbox::new("jones", Domain::new("foo.com").unwrap().into()).unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_time_stamp_line_untrusted() {
+        let tests: &[&[u8]] = &[
+            b"Received: from bar.com by foo.com ; \r\n\r\n",
+            b"Received: from bar.com ((((((((((((((((((((((((((((((((((((((((((((",
+            b"Received: from (\\",
+            b"Received: \xff\xfe by",
+        ];
+
+        for test in tests {
+            let input = [*test, b"\r\n\r\n"].concat();
+            assert!(time_stamp_line(&input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_extended_domain() {
+        let (rem, got) = extended_domain(b"[192.0.2.1] (bar.com [192.0.2.1]) by").unwrap();
+        assert_eq!(
+            got,
+            ExtendedDomain::AddressWithTcpInfo {
+                address: Ipv4Addr::new(192, 0, 2, 1).into(),
+                tcp_info: TcpInfo {
+                    reverse_name: Some(Domain::new("bar.com").unwrap()),
+                    address: Ipv4Addr::new(192, 0, 2, 1).into(),
+                },
+            }
+        );
+        assert_eq!(rem, b" by");
+    }
+
+    #[test]
+    fn test_serialize_time_stamp_line() {
+        let tests = [
+            (
+                Received::builder(
+                    DomainOrAddress::Domain(Domain::new("bar.com").unwrap()),
+                    IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                    "foo.com",
+                )
+                .build(DATE_TIME)
+                .unwrap(),
+                b"Received: from bar.com ([192.0.2.1]) by foo.com;\r\n\tThu, 21 May 1998 05:33:29 -0700\r\n"
+                    .as_ref(),
+            ),
+            (
+                Received::builder(
+                    DomainOrAddress::Domain(Domain::new("client.example.org").unwrap()),
+                    IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+                    "mx.example.com",
+                )
+                .peer_name("client.example.org")
+                .protocol(Protocol::ESMTPS)
+                .id("4A1B2C3D")
+                .recipient(
+                    Mailbox::new("jones", Domain::new("example.com").unwrap().into()).unwrap(),
+                )
+                .build(DATE_TIME)
+                .unwrap(),
+                b"Received: from client.example.org (client.example.org [IPv6:2001:db8::1])\r\n\
+                \tby mx.example.com with ESMTPS id 4A1B2C3D for <jones@example.com>;\r\n\
+                \tThu, 21 May 1998 05:33:29 -0700\r\n"
+                    .as_ref(),
+            ),
+            // Quoted local parts are accepted like in "RCPT TO".
+            (
+                Received::builder(
+                    DomainOrAddress::Domain(Domain::new("bar.com").unwrap()),
+                    IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                    "foo.com",
+                )
+                .recipient(
+                    Mailbox::new("john doe", Domain::new("foo.com").unwrap().into()).unwrap(),
+                )
+                .build(DATE_TIME)
+                .unwrap(),
+                b"Received: from bar.com ([192.0.2.1]) by foo.com for <\"john doe\"@foo.com>;\r\n\
+                \tThu, 21 May 1998 05:33:29 -0700\r\n"
+                    .as_ref(),
+            ),
+            (
+                Received::builder(
+                    DomainOrAddress::Address(Ipv4Addr::new(192, 0, 2, 1).into()),
+                    IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                    "a-very-long-host-name-that-does-not-fit.on-a-single-line.example.com",
+                )
+                .build(DATE_TIME)
+                .unwrap(),
+                b"Received: from [192.0.2.1] ([192.0.2.1])\r\n\
+                \tby a-very-long-host-name-that-does-not-fit.on-a-single-line.example.com;\r\n\
+                \tThu, 21 May 1998 05:33:29 -0700\r\n"
+                    .as_ref(),
+            ),
+            // U-labels are written as A-labels.
+            (
+                Received::builder(
+                    DomainOrAddress::Domain(Domain::new("bücher.example").unwrap()),
+                    IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                    "mx.bücher.example",
+                )
+                .peer_name("bücher.example")
+                .build(DATE_TIME)
+                .unwrap(),
+                b"Received: from xn--bcher-kva.example (xn--bcher-kva.example [192.0.2.1])\r\n\
+                \tby mx.xn--bcher-kva.example; Thu, 21 May 1998 05:33:29 -0700\r\n"
+                    .as_ref(),
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let mut got = Vec::new();
+            test.serialize(&mut got).unwrap();
+            assert_eq!(
+                std::str::from_utf8(expected).unwrap(),
+                std::str::from_utf8(&got).unwrap()
+            );
+
+            for line in got.split(|b| *b == b'\n') {
+                assert!(line.len() <= 78 + 1);
+            }
+
+            got.extend_from_slice(b"\r\n");
+            let (rem, parsed) = time_stamp_line(&got).unwrap();
+            assert_eq!(rem, b"\r\n");
+            assert_eq!(parsed, test);
+        }
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let builder = Received::builder(
+            DomainOrAddress::Domain(Domain::new("bar.com").unwrap()),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            "foo.com",
+        );
+
+        assert!(builder
+            .clone()
+            .id("ABC\r\nX-Injected: 1")
+            .build(DATE_TIME)
+            .is_err());
+        assert!(builder
+            .clone()
+            .protocol(Protocol::Other("E SMTP".into()))
+            .build(DATE_TIME)
+            .is_err());
+        assert!(builder.peer_name("-bar.com").build(DATE_TIME).is_err());
+        assert!(Received::builder(
+            DomainOrAddress::Domain(Domain::new("bar.com").unwrap()),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            "foo.com\r\n",
+        )
+        .build(DATE_TIME)
+        .is_err());
+    }
+
+    #[test]
+    fn test_serialize_invalid() {
+        let received = Received::builder(
+            DomainOrAddress::Domain(Domain::new("bar.com").unwrap()),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            "foo.com",
+        )
+        .build(DATE_TIME)
+        .unwrap();
+
+        let valid = [
+            Received {
+                id: Some(ReceivedId::MsgId(
+                    MessageId::new("ABC.123", "foo.com").unwrap(),
+                )),
+                ..received.clone()
+            },
+            Received {
+                for_path: Some(Path::from(
+                    Mailbox::new(
+                        "john doe",
+                        DomainOrAddress::Address(
+                            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).into(),
+                        ),
+                    )
+                    .unwrap(),
+                )),
+                ..received.clone()
+            },
+            Received {
+                additional_clauses: vec![AdditionalClause {
+                    name: "x-note".into(),
+                    value: AtomOrQuoted::Quoted("a \"b\"".into()),
+                }],
+                ..received.clone()
+            },
+        ];
+
+        for received in valid {
+            assert!(received.validate().is_ok());
+
+            let mut out = Vec::new();
+            received.serialize(&mut out).unwrap();
+            out.extend_from_slice(b"\r\n");
+            let (_, parsed) = time_stamp_line(&out).unwrap();
+            assert_eq!(parsed, received);
+        }
+
+        // Hand-built stamps must not inject header fields.
+        let invalid = [
+            Received {
+                via: Some("TCP\r\nX-Injected: 1".into()),
+                ..received.clone()
+            },
+            Received {
+                with: Some(Protocol::Other("ESMTP\r\nX-Injected: 1".into())),
+                ..received.clone()
+            },
+            Received {
+                id: Some(ReceivedId::Atom("ABC\r\nX-Injected: 1".into())),
+                ..received.clone()
+            },
+            Received {
+                id: Some(ReceivedId::Atom("<ABC.123@foo.com>".into())),
+                ..received.clone()
+            },
+            Received {
+                additional_clauses: vec![AdditionalClause {
+                    name: "x-note".into(),
+                    value: AtomOrQuoted::Quoted("a\r\nX-Injected: 1".into()),
+                }],
+                ..received
+            },
+        ];
+
+        for received in invalid {
+            assert!(received.validate().is_err());
+
+            let mut out = Vec::new();
+            let error = received.serialize(&mut out).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(out.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_protocol() {
+        let tests: &[(&[u8], Protocol)] = &[
+            (b"SMTP ", Protocol::SMTP),
+            (b"esmtp ", Protocol::ESMTP),
+            (b"ESMTPS ", Protocol::ESMTPS),
+            (b"ESMTPSA ", Protocol::ESMTPSA),
+            (b"LMTPA ", Protocol::LMTPA),
+            (b"UTF8SMTPS ", Protocol::UTF8SMTPS),
+            (b"UTF8LMTPSA ", Protocol::UTF8LMTPSA),
+            (b"HTTP ", Protocol::Other("HTTP".into())),
+        ];
+
+        for (test, expected) in tests {
+            let (rem, got) = protocol(test).unwrap();
+            assert_eq!(rem, b" ");
+            assert_eq!(got, *expected);
+        }
+
+        assert!(Protocol::ESMTPSA.is_authenticated());
+        assert!(Protocol::ESMTPSA.is_encrypted());
+        assert!(Protocol::UTF8LMTPA.is_authenticated());
+        assert!(!Protocol::UTF8LMTPA.is_encrypted());
+        assert!(Protocol::UTF8LMTPA.is_utf8());
+        assert!(!Protocol::ESMTP.is_authenticated());
+        assert!(!Protocol::ESMTP.is_encrypted());
+        assert!(!Protocol::Other("ESMTPSX".into()).is_encrypted());
+    }
+
+    #[test]
+    fn test_received_chain() {
+        let header = b"\
+Received: from mx.example.com (mx.example.com [198.51.100.7])\r\n\
+\tby mail.example.net with ESMTPS id 1A2B3C; Thu, 21 May 1998 05:35:00 -0700\r\n\
+X-Spam: no\r\n\
+Received: from garbage\r\n\
+Received: from client.example.org (client.example.org [IPv6:2001:db8::1])\r\n\
+\tby mx.example.com with ESMTPSA id 4D5E6F; Thu, 21 May 1998 14:33:29 +0200\r\n\
+received: from [192.0.2.1] ([192.0.2.1]) by client.example.org with SMTP;\r\n\
+\tThu, 21 May 1998 12:33:00 +0000\r\n\
+Subject: Received: from nowhere\r\n\
+\r\n\
+Received: from body by body; Thu, 21 May 1998 12:33:00 +0000\r\n";
+
+        let chain = received_chain(header);
+        assert_eq!(chain.len(), 4);
+
+        let hop = chain[0].as_ref().unwrap();
+        assert_eq!(
+            hop.from,
+            DomainOrAddress::Domain(Domain::new("mx.example.com").unwrap())
+        );
+        assert_eq!(
+            hop.by,
+            DomainOrAddress::Domain(Domain::new("mail.example.net").unwrap())
+        );
+        assert_eq!(hop.ip, Some(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7))));
+        assert_eq!(hop.protocol, Some(Protocol::ESMTPS));
+        assert!(hop.tls);
+        assert!(!hop.authenticated);
+        assert_eq!(hop.delay, None);
+
+        assert_eq!(
+            chain[1],
+            Err(MalformedStamp {
+                raw: b"Received: from garbage\r\n".to_vec(),
+                error: StampError::Syntax,
+            })
+        );
+
+        let hop = chain[2].as_ref().unwrap();
+        assert_eq!(
+            hop.ip,
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
+        );
+        assert!(hop.tls);
+        assert!(hop.authenticated);
+        assert_eq!(hop.delay, Some(29));
+
+        let hop = chain[3].as_ref().unwrap();
+        assert_eq!(
+            hop.from,
+            DomainOrAddress::Address(Ipv4Addr::new(192, 0, 2, 1).into())
+        );
+        assert_eq!(hop.protocol, Some(Protocol::SMTP));
+        assert!(!hop.tls);
+        assert_eq!(hop.delay, None);
+    }
+
+    #[test]
+    fn test_received_chain_date_time() {
+        let header = b"\
+Received: from a.test by b.test; Thu, 21 May 98 05:33:29 -0700\r\n\
+Received: from a.test by b.test; Thu, 21 May 1998 05:33:29 PDT\r\n\
+Received: from a.test by b.test; Fri, 21 May 1998 05:33:29 -0700\r\n\
+Received: from a.test by b.test; Thu, 31 Apr 1998 05:33:29 -0700\r\n\
+Received: from a.test by b.test; Thu, 21 May 1998 05:33:29 -0700\r\n\
+\r\n";
+
+        let errors: Vec<_> = received_chain(header)
+            .into_iter()
+            .map(|hop| hop.err().map(|malformed| malformed.error))
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                Some(StampError::ObsoleteDateTime),
+                Some(StampError::ObsoleteDateTime),
+                Some(StampError::InvalidDateTime(InvalidDateTime::DayOfWeek)),
+                Some(StampError::InvalidDateTime(InvalidDateTime::Day)),
+                None,
+            ]
+        );
+    }
+}
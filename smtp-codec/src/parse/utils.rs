@@ -8,3 +8,44 @@ pub fn single_line(input: &[u8]) -> IResult<&[u8], String> {
 
     Ok((rem, String::from_utf8(line.to_vec()).unwrap()))
 }
+
+/// Split a header section into its (raw) fields.
+///
+/// Every field includes its trailing CRLF and all continuation lines. Splitting stops at the
+/// first empty line, i.e., the separator between header section and body.
+pub(crate) fn header_fields(mut input: &[u8]) -> Vec<&[u8]> {
+    let mut fields = Vec::new();
+
+    while !input.is_empty() && !input.starts_with(b"\r\n") {
+        let mut end = 0;
+
+        loop {
+            match input[end..].windows(2).position(|window| window == b"\r\n") {
+                Some(position) => {
+                    end += position + 2;
+
+                    if !matches!(input.get(end), Some(b' ' | b'\t')) {
+                        break;
+                    }
+                }
+                None => {
+                    end = input.len();
+                    break;
+                }
+            }
+        }
+
+        let (field, remaining) = input.split_at(end);
+        fields.push(field);
+        input = remaining;
+    }
+
+    fields
+}
+
+/// Does the raw header `field` have the (case-insensitive) `name`?
+pub(crate) fn has_field_name(field: &[u8], name: &[u8]) -> bool {
+    field.len() > name.len()
+        && field[..name.len()].eq_ignore_ascii_case(name)
+        && field[name.len()] == b':'
+}
@@ -1,15 +1,53 @@
 use std::borrow::Cow;
 
-pub(crate) fn unescape_quoted(escaped: &str) -> Cow<str> {
-    let mut unescaped = Cow::Borrowed(escaped);
-
-    if unescaped.contains("\\\\") {
-        unescaped = Cow::Owned(unescaped.replace("\\\\", "\\"));
+/// Remove the backslash of every quoted-pair, e.g., `john\ smith` becomes `john smith`.
+///
+/// quoted-pairSMTP = %d92 %d32-126
+///
+/// Note: The input must be the content of a valid Quoted-string, i.e., every backslash starts a
+/// quoted-pair. A trailing backslash is kept as is.
+pub(crate) fn unescape_quoted(escaped: &str) -> Cow<'_, str> {
+    if !escaped.contains('\\') {
+        return Cow::Borrowed(escaped);
     }
 
-    if unescaped.contains("\\\"") {
-        unescaped = Cow::Owned(unescaped.replace("\\\"", "\""));
+    let mut unescaped = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' => unescaped.push(chars.next().unwrap_or('\\')),
+            _ => unescaped.push(character),
+        }
     }
 
-    unescaped
+    Cow::Owned(unescaped)
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::unescape_quoted;
+
+    #[test]
+    fn test_unescape_quoted() {
+        let tests = [
+            ("", ""),
+            ("john smith", "john smith"),
+            ("john\\ smith", "john smith"),
+            ("\\a\\b\\c", "abc"),
+            ("\\\\", "\\"),
+            ("\\\"", "\""),
+            ("\\\\\\\"", "\\\""),
+            ("\\\\\\\\", "\\\\"),
+            ("\\~\\!", "~!"),
+        ];
+
+        for (test, expected) in tests {
+            assert_eq!(unescape_quoted(test), expected);
+        }
+
+        assert!(matches!(unescape_quoted("no escapes"), Cow::Borrowed(_)));
+    }
 }
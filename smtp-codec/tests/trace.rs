@@ -27,7 +27,7 @@ fn parse_trace(mut trace: &[u8]) {
                 println!("S: {:?}", rsp);
                 trace = rem;
             }
-            Command::Data { .. } => {
+            Command::Data => {
                 let (rem, rsp) = reply_lines(trace).unwrap();
                 println!("S: {:?}", rsp);
                 trace = rem;
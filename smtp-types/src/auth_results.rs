@@ -0,0 +1,546 @@
+//! Message Header Field for Indicating Message Authentication Status (RFC 8601)
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{escape_quoted, is_atom, is_domain, write_folded};
+
+/// authres-header-field = "Authentication-Results:" authres-payload
+///
+/// authres-payload = [CFWS] authserv-id
+///                   [ CFWS authres-version ]
+///                   ( no-result / 1*resinfo ) [CFWS] CRLF
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "UncheckedAuthenticationResults")
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthenticationResults {
+    /// Identifier of the authentication service that produced the results, e.g.,
+    /// "mx.example.com".
+    pub authserv_id: String,
+    /// authres-version = 1*DIGIT [CFWS]
+    pub version: Option<u32>,
+    /// Results of the authentication methods. Empty when no method was applied ("none").
+    pub results: Vec<MethodResult>,
+}
+
+/// Deserialized fields of [`AuthenticationResults`] that are checked by
+/// [`AuthenticationResults::validate`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedAuthenticationResults {
+    authserv_id: String,
+    version: Option<u32>,
+    results: Vec<MethodResult>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedAuthenticationResults> for AuthenticationResults {
+    type Error = InvalidAuthenticationResults;
+
+    fn try_from(value: UncheckedAuthenticationResults) -> Result<Self, Self::Error> {
+        let results = AuthenticationResults {
+            authserv_id: value.authserv_id,
+            version: value.version,
+            results: value.results,
+        };
+        results.validate()?;
+
+        Ok(results)
+    }
+}
+
+impl AuthenticationResults {
+    /// Start building the results of the authentication service `authserv_id`.
+    pub fn builder<A>(authserv_id: A) -> AuthenticationResultsBuilder
+    where
+        A: Into<String>,
+    {
+        AuthenticationResultsBuilder {
+            authserv_id: authserv_id.into(),
+            results: vec![],
+        }
+    }
+
+    /// Were the results added by the authentication service `authserv_id`?
+    ///
+    /// Results of other authentication services can be forged and should only be trusted
+    /// when their authserv-id is known to be trustworthy (see RFC 8601, Section 5).
+    pub fn is_from(&self, authserv_id: &str) -> bool {
+        self.authserv_id.eq_ignore_ascii_case(authserv_id)
+    }
+
+    /// Results of the given `method`.
+    pub fn results_of<'a>(&'a self, method: &'a Method) -> impl Iterator<Item = &'a MethodResult> {
+        self.results
+            .iter()
+            .filter(move |result| &result.method == method)
+    }
+
+    /// Check that the authserv-id, the methods, the results, and the properties are
+    /// well-formed, e.g., that no reason or pvalue contains a line break.
+    ///
+    /// Results created by [`AuthenticationResultsBuilder`] are always valid.
+    pub fn validate(&self) -> Result<(), InvalidAuthenticationResults> {
+        check(is_value(&self.authserv_id), "authserv-id")?;
+
+        for result in &self.results {
+            check(is_keyword(result.method.name()), "method")?;
+            check(is_keyword(result.result.name()), "result")?;
+
+            if let Some(ref reason) = result.reason {
+                check(is_value(reason), "reason")?;
+            }
+
+            for property in &result.properties {
+                check(is_keyword(&property.ptype), "ptype")?;
+                check(is_keyword(&property.property), "property")?;
+                check(is_value(&property.value), "pvalue")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serialize as "Authentication-Results:" header field (including the trailing CRLF).
+    ///
+    /// The field is folded between method results and properties to keep lines within 78
+    /// characters.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] when the results are invalid, see
+    /// [`AuthenticationResults::validate`].
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        self.validate()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+        let mut units = Vec::new();
+
+        let mut authserv_id = value(&self.authserv_id);
+        if let Some(version) = self.version {
+            authserv_id.push_str(&format!(" {}", version));
+        }
+        authserv_id.push(';');
+        units.push(authserv_id);
+
+        if self.results.is_empty() {
+            units.push("none".to_owned());
+        }
+
+        for (index, result) in self.results.iter().enumerate() {
+            // methodspec = [CFWS] method [CFWS] "=" [CFWS] result
+            match result.method_version {
+                Some(version) => units.push(format!(
+                    "{}/{}={}",
+                    result.method.name(),
+                    version,
+                    result.result.name()
+                )),
+                None => units.push(format!("{}={}", result.method.name(), result.result.name())),
+            }
+
+            // reasonspec = "reason" [CFWS] "=" [CFWS] value
+            if let Some(reason) = &result.reason {
+                units.push(format!("reason={}", value(reason)));
+            }
+
+            // propspec = ptype [CFWS] "." [CFWS] property [CFWS] "=" pvalue
+            for property in &result.properties {
+                units.push(format!(
+                    "{}.{}={}",
+                    property.ptype,
+                    property.property,
+                    pvalue(&property.value)
+                ));
+            }
+
+            if index + 1 < self.results.len() {
+                if let Some(last) = units.last_mut() {
+                    last.push(';');
+                }
+            }
+        }
+
+        write_folded(writer, "Authentication-Results", &units, 78)
+    }
+}
+
+/// Builder for [`AuthenticationResults`].
+///
+/// See [`AuthenticationResults::builder`].
+#[derive(Clone, Debug)]
+pub struct AuthenticationResultsBuilder {
+    authserv_id: String,
+    results: Vec<MethodResult>,
+}
+
+impl AuthenticationResultsBuilder {
+    /// Add the result of an authentication method.
+    pub fn result(mut self, result: MethodResult) -> Self {
+        self.results.push(result);
+        self
+    }
+
+    /// Build the results. The version is omitted because version 1 is the only version.
+    ///
+    /// Fails when the authserv-id, a reason, or a pvalue is empty or contains characters
+    /// other than printable ASCII, or when a method, result, ptype, or property is not a
+    /// keyword, see [`AuthenticationResults::validate`].
+    pub fn build(self) -> Result<AuthenticationResults, InvalidAuthenticationResults> {
+        let results = AuthenticationResults {
+            authserv_id: self.authserv_id,
+            version: None,
+            results: self.results,
+        };
+        results.validate()?;
+
+        Ok(results)
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidAuthenticationResults {
+    field: &'static str,
+}
+
+impl fmt::Display for InvalidAuthenticationResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {} in Authentication-Results", self.field)
+    }
+}
+
+impl std::error::Error for InvalidAuthenticationResults {}
+
+fn check(valid: bool, field: &'static str) -> Result<(), InvalidAuthenticationResults> {
+    match valid {
+        true => Ok(()),
+        false => Err(InvalidAuthenticationResults { field }),
+    }
+}
+
+/// resinfo = [CFWS] ";" methodspec [ CFWS reasonspec ] [ CFWS 1*propspec ]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodResult {
+    pub method: Method,
+    /// method-version = 1*DIGIT [CFWS]
+    pub method_version: Option<u32>,
+    pub result: AuthResult,
+    /// reasonspec = "reason" [CFWS] "=" [CFWS] value
+    pub reason: Option<String>,
+    pub properties: Vec<Property>,
+}
+
+impl MethodResult {
+    pub fn new(method: Method, result: AuthResult) -> Self {
+        MethodResult {
+            method,
+            method_version: None,
+            result,
+            reason: None,
+            properties: vec![],
+        }
+    }
+
+    /// Human-readable explanation of the result.
+    pub fn reason<R: Into<String>>(mut self, reason: R) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Property of the message that was evaluated, e.g., `("smtp", "mailfrom", "example.net")`.
+    pub fn property<T, P, V>(mut self, ptype: T, property: P, value: V) -> Self
+    where
+        T: Into<String>,
+        P: Into<String>,
+        V: Into<String>,
+    {
+        self.properties.push(Property {
+            ptype: ptype.into(),
+            property: property.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Value of the first property with the given `ptype` and `property` (case-insensitive).
+    pub fn property_value(&self, ptype: &str, property: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|candidate| {
+                candidate.ptype.eq_ignore_ascii_case(ptype)
+                    && candidate.property.eq_ignore_ascii_case(property)
+            })
+            .map(|property| property.value.as_str())
+    }
+}
+
+/// propspec = ptype [CFWS] "." [CFWS] property [CFWS] "=" pvalue
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Property {
+    /// ptype = Keyword, e.g., "smtp" or "header".
+    pub ptype: String,
+    /// property = special-smtp-verb / Keyword, e.g., "mailfrom" or "d".
+    pub property: String,
+    /// pvalue = [CFWS] ( value / [ [ local-part ] "@" ] domain-name ) [CFWS]
+    pub value: String,
+}
+
+/// Authentication methods registered with IANA.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Method {
+    /// SMTP AUTH [RFC4954]
+    Auth,
+    /// DomainKeys Identified Mail [RFC6376]
+    Dkim,
+    /// Domain-based Message Authentication, Reporting, and Conformance [RFC7489]
+    Dmarc,
+    /// Authenticated Received Chain [RFC8617]
+    Arc,
+    /// Reverse IP lookup [RFC8601]
+    IpRev,
+    /// Sender Policy Framework [RFC7208]
+    Spf,
+    /// Other
+    Other(String),
+}
+
+impl Method {
+    pub fn name(&self) -> &str {
+        match self {
+            Method::Auth => "auth",
+            Method::Dkim => "dkim",
+            Method::Dmarc => "dmarc",
+            Method::Arc => "arc",
+            Method::IpRev => "iprev",
+            Method::Spf => "spf",
+            Method::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for Method {
+    /// Method names are case-insensitive.
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "auth" => Method::Auth,
+            "dkim" => Method::Dkim,
+            "dmarc" => Method::Dmarc,
+            "arc" => Method::Arc,
+            "iprev" => Method::IpRev,
+            "spf" => Method::Spf,
+            _ => Method::Other(value.to_owned()),
+        }
+    }
+}
+
+/// Result codes registered with IANA.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AuthResult {
+    /// The method was not applied.
+    None,
+    Pass,
+    Fail,
+    SoftFail,
+    /// Deprecated synonym for [`AuthResult::Fail`] used by SPF.
+    HardFail,
+    Neutral,
+    /// A temporary error prevented the evaluation.
+    TempError,
+    /// A permanent error prevented the evaluation.
+    PermError,
+    /// The result was overridden by local policy.
+    Policy,
+    /// Other
+    Other(String),
+}
+
+impl AuthResult {
+    pub fn name(&self) -> &str {
+        match self {
+            AuthResult::None => "none",
+            AuthResult::Pass => "pass",
+            AuthResult::Fail => "fail",
+            AuthResult::SoftFail => "softfail",
+            AuthResult::HardFail => "hardfail",
+            AuthResult::Neutral => "neutral",
+            AuthResult::TempError => "temperror",
+            AuthResult::PermError => "permerror",
+            AuthResult::Policy => "policy",
+            AuthResult::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for AuthResult {
+    /// Result codes are case-insensitive.
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => AuthResult::None,
+            "pass" => AuthResult::Pass,
+            "fail" => AuthResult::Fail,
+            "softfail" => AuthResult::SoftFail,
+            "hardfail" => AuthResult::HardFail,
+            "neutral" => AuthResult::Neutral,
+            "temperror" => AuthResult::TempError,
+            "permerror" => AuthResult::PermError,
+            "policy" => AuthResult::Policy,
+            _ => AuthResult::Other(value.to_owned()),
+        }
+    }
+}
+
+/// Keyword = Ldh-str
+fn is_keyword(value: &str) -> bool {
+    is_domain(value) && !value.contains('.')
+}
+
+/// Values are serialized as token or quoted-string. Thus, only printable characters are
+/// allowed.
+fn is_value(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| matches!(b, 32..=126))
+}
+
+/// token = 1*<any (US-ASCII) CHAR except SPACE, CTLs, or tspecials>
+///
+/// tspecials = "(" / ")" / "<" / ">" / "@" / "," / ";" / ":" / "\" / <"> / "/" / "[" / "]" /
+///             "?" / "="
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| matches!(b, 33..=126) && !b"()<>@,;:\\\"/[]?=".contains(&b))
+}
+
+/// value = token / quoted-string
+fn value(value: &str) -> String {
+    match is_token(value) {
+        true => value.to_owned(),
+        false => format!("\"{}\"", escape_quoted(value)),
+    }
+}
+
+/// pvalue = [CFWS] ( value / [ [ local-part ] "@" ] domain-name ) [CFWS]
+fn pvalue(value: &str) -> String {
+    let is_address = match value.rsplit_once('@') {
+        Some((local_part, domain)) => {
+            (local_part.is_empty() || local_part.split('.').all(is_atom)) && is_domain(domain)
+        }
+        None => false,
+    };
+
+    match is_address {
+        true => value.to_owned(),
+        false => self::value(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthResult, AuthenticationResults, Method, MethodResult};
+
+    #[test]
+    fn test_serialize() {
+        let results = AuthenticationResults::builder("mx.example.com")
+            .result(MethodResult::new(Method::Spf, AuthResult::Pass).property(
+                "smtp",
+                "mailfrom",
+                "sender@example.net",
+            ))
+            .result(
+                MethodResult::new(Method::Dkim, AuthResult::Fail)
+                    .reason("signature did not verify")
+                    .property("header", "d", "example.net")
+                    .property("header", "i", "@example.net"),
+            )
+            .result(MethodResult::new(Method::Dmarc, AuthResult::None))
+            .build()
+            .unwrap();
+
+        let mut out = Vec::new();
+        results.serialize(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Authentication-Results: mx.example.com; spf=pass\r\n\
+             \tsmtp.mailfrom=sender@example.net; dkim=fail reason=\"signature did not verify\"\r\n\
+             \theader.d=example.net header.i=@example.net; dmarc=none\r\n"
+        );
+
+        let mut out = Vec::new();
+        AuthenticationResults::builder("mx.example.com")
+            .build()
+            .unwrap()
+            .serialize(&mut out)
+            .unwrap();
+        assert_eq!(out, b"Authentication-Results: mx.example.com; none\r\n");
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(AuthenticationResults::builder("").build().is_err());
+        assert!(AuthenticationResults::builder("mx\r\n").build().is_err());
+        assert!(AuthenticationResults::builder("mx.example.com")
+            .result(MethodResult::new(
+                Method::Other("x y".into()),
+                AuthResult::Pass
+            ))
+            .build()
+            .is_err());
+        assert!(AuthenticationResults::builder("mx.example.com")
+            .result(
+                MethodResult::new(Method::Spf, AuthResult::Pass)
+                    .property("smtp.x", "mailfrom", "a")
+            )
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_serialize_invalid() {
+        let mut results = AuthenticationResults::builder("mx.example.com")
+            .result(MethodResult::new(Method::Spf, AuthResult::Pass))
+            .build()
+            .unwrap();
+        results.results[0].reason = Some("a\r\nX-Injected: yes".into());
+        assert!(results.validate().is_err());
+        assert_eq!(
+            results.serialize(&mut Vec::new()).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        results.results[0].reason = None;
+        results.results[0] =
+            results.results[0]
+                .clone()
+                .property("smtp", "mailfrom", "a@example.net\r\n");
+        assert!(results.serialize(&mut Vec::new()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let results = AuthenticationResults::builder("mx.example.com")
+            .result(MethodResult::new(Method::Spf, AuthResult::Pass).reason("ok"))
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&results).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AuthenticationResults>(&json).unwrap(),
+            results
+        );
+
+        let json = json.replace("\"ok\"", "\"a\\r\\nb\"");
+        assert!(serde_json::from_str::<AuthenticationResults>(&json).is_err());
+    }
+}
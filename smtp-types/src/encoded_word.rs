@@ -0,0 +1,197 @@
+//! Encoded-words in message headers (RFC 2047)
+
+use std::io::{self, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    imf::InvalidRawField,
+    utils::{is_field_name, write_folded},
+};
+
+/// encoding = token  ; "B" or "Q"
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// 4.1.  The "B" encoding, i.e., base64.
+    B,
+    /// 4.2.  The "Q" encoding, similar to quoted-printable.
+    Q,
+}
+
+/// An encoded-word must not be longer than 75 characters.
+const MAX_ENCODED_WORD_LENGTH: usize = 75;
+
+/// A line containing an encoded-word must not be longer than 76 characters.
+const MAX_LINE_LENGTH: usize = 76;
+
+/// Encode `text` as a sequence of UTF-8 encoded-words.
+///
+/// Every encoded-word is at most 75 characters long and characters are never split between
+/// encoded-words. Decoders ignore white space between adjacent encoded-words, i.e., the
+/// encoded-words can be joined with single spaces or folded.
+pub fn encode(text: &str, encoding: Encoding) -> Vec<String> {
+    encode_with_limits(
+        text,
+        encoding,
+        MAX_ENCODED_WORD_LENGTH,
+        MAX_ENCODED_WORD_LENGTH,
+    )
+}
+
+/// Serialize a header field, e.g., "Subject", with its unstructured `text` as encoded-words.
+///
+/// The field is folded such that no line is longer than 76 characters (excluding CRLF).
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] when `name` is not a field-name, i.e., when it
+/// is empty or contains ":", white space, control, or non-ASCII characters.
+pub fn serialize_field(
+    writer: &mut impl Write,
+    name: &str,
+    text: &str,
+    encoding: Encoding,
+) -> std::io::Result<()> {
+    if !is_field_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            InvalidRawField::Name,
+        ));
+    }
+
+    // The first line starts with "<name>: ", continuation lines with a single WSP.
+    let first_limit = MAX_LINE_LENGTH.saturating_sub(name.len() + 2);
+    let words = encode_with_limits(text, encoding, first_limit, MAX_ENCODED_WORD_LENGTH);
+
+    write_folded(writer, name, &words, MAX_LINE_LENGTH)
+}
+
+fn encode_with_limits(
+    text: &str,
+    encoding: Encoding,
+    first_limit: usize,
+    limit: usize,
+) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    let mut current_limit = first_limit;
+
+    for character in text.chars() {
+        let mut candidate = chunk.clone();
+        candidate.push(character);
+
+        if !chunk.is_empty() && encode_word(&candidate, encoding).len() > current_limit {
+            words.push(encode_word(&chunk, encoding));
+            chunk = character.to_string();
+            current_limit = limit;
+        } else {
+            chunk = candidate;
+        }
+    }
+
+    if !chunk.is_empty() {
+        words.push(encode_word(&chunk, encoding));
+    }
+
+    words
+}
+
+/// encoded-word = "=?" charset "?" encoding "?" encoded-text "?="
+fn encode_word(text: &str, encoding: Encoding) -> String {
+    match encoding {
+        Encoding::B => format!("=?UTF-8?B?{}?=", encode_b(text.as_bytes())),
+        Encoding::Q => format!("=?UTF-8?Q?{}?=", encode_q(text.as_bytes())),
+    }
+}
+
+fn encode_b(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = match *chunk {
+            [a] => u32::from(a) << 16,
+            [a, b] => (u32::from(a) << 16) | (u32::from(b) << 8),
+            [a, b, c] => (u32::from(a) << 16) | (u32::from(b) << 8) | u32::from(c),
+            _ => unreachable!(),
+        };
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Only characters allowed in all contexts, i.e., also in a phrase, are written literally.
+///
+/// See RFC 2047, Section 5 (3).
+fn encode_q(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() * 3);
+
+    for byte in bytes {
+        match byte {
+            b' ' => encoded.push('_'),
+            b'!' | b'*' | b'+' | b'-' | b'/' => encoded.push(*byte as char),
+            _ if byte.is_ascii_alphanumeric() => encoded.push(*byte as char),
+            _ => encoded.push_str(&format!("={:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, serialize_field, Encoding};
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("", Encoding::B), Vec::<String>::new());
+        assert_eq!(encode("André", Encoding::B), vec!["=?UTF-8?B?QW5kcsOp?="]);
+        assert_eq!(
+            encode("André Pirard", Encoding::Q),
+            vec!["=?UTF-8?Q?Andr=C3=A9_Pirard?="]
+        );
+        assert_eq!(
+            encode("a=b?c_d", Encoding::Q),
+            vec!["=?UTF-8?Q?a=3Db=3Fc=5Fd?="]
+        );
+
+        for encoding in [Encoding::B, Encoding::Q] {
+            let text = "Grüße aus Köln! ".repeat(10);
+            let words = encode(&text, encoding);
+            assert!(words.len() > 1);
+            assert!(words.iter().all(|word| word.len() <= 75));
+        }
+    }
+
+    #[test]
+    fn test_serialize_field() {
+        let text = "Ünïcödé ".repeat(10);
+
+        let mut out = Vec::new();
+        serialize_field(&mut out, "Subject", &text, Encoding::Q).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("Subject: =?UTF-8?Q?"));
+        assert!(out.ends_with("?=\r\n"));
+        let lines: Vec<_> = out.trim_end().split("\r\n").collect();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.len() <= 76));
+        assert!(lines[1..].iter().all(|line| line.starts_with('\t')));
+
+        for name in ["", "Sub ject", "Subject:", "Subject\r\nBcc", "Sübject"] {
+            let mut out = Vec::new();
+            let error = serialize_field(&mut out, name, "text", Encoding::B).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(out.is_empty());
+        }
+    }
+}
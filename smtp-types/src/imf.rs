@@ -0,0 +1,1186 @@
+//! Internet Message Format (RFC 5322)
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    io::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    utils::{escape_quoted, is_address, is_atom, is_field_name, parse_address_literal},
+    Domain, DomainOrAddress,
+};
+
+/// A header field of the header section.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// Field name as it appears in the message, e.g., "Subject".
+    pub name: String,
+    /// The raw field (including the field name, folding, and the trailing CRLF).
+    pub raw: Vec<u8>,
+    pub value: FieldValue,
+}
+
+/// 3.6.  Field Definitions
+///
+/// Unstructured values are unfolded and stripped of leading and trailing white space.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldValue {
+    /// orig-date = "Date:" date-time CRLF
+    Date(DateTime),
+    /// from = "From:" mailbox-list CRLF
+    From(Vec<Mailbox>),
+    /// sender = "Sender:" mailbox CRLF
+    Sender(Mailbox),
+    /// reply-to = "Reply-To:" address-list CRLF
+    ReplyTo(Vec<Address>),
+    /// to = "To:" address-list CRLF
+    To(Vec<Address>),
+    /// cc = "Cc:" address-list CRLF
+    Cc(Vec<Address>),
+    /// bcc = "Bcc:" [address-list / CFWS] CRLF
+    Bcc(Vec<Address>),
+    /// message-id = "Message-ID:" msg-id CRLF
+    MessageId(MessageId),
+    /// in-reply-to = "In-Reply-To:" 1*msg-id CRLF
+    InReplyTo(Vec<MessageId>),
+    /// references = "References:" 1*msg-id CRLF
+    References(Vec<MessageId>),
+    /// subject = "Subject:" unstructured CRLF
+    ///
+    /// Encoded-words (RFC 2047) are decoded.
+    Subject(String),
+    /// Any other field, e.g., optional-field = field-name ":" unstructured CRLF
+    Unstructured(String),
+}
+
+/// A header field given by its name and unfolded value, e.g., a trace field that is prepended
+/// to a message.
+///
+/// The value is folded on serialization.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "UncheckedRawField")
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawField {
+    name: String,
+    value: String,
+}
+
+/// Deserialized fields of a [`RawField`] that are validated by [`RawField::new`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedRawField {
+    name: String,
+    value: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedRawField> for RawField {
+    type Error = InvalidRawField;
+
+    fn try_from(value: UncheckedRawField) -> Result<Self, Self::Error> {
+        RawField::new(value.name, value.value)
+    }
+}
+
+impl RawField {
+    /// Lines should not be longer than 78 characters (excluding CRLF).
+    pub const RECOMMENDED_LINE_LENGTH: usize = 78;
+
+    /// Lines must not be longer than 998 characters (excluding CRLF).
+    pub const MAX_LINE_LENGTH: usize = 998;
+
+    /// Create a header field. Leading and trailing white space of `value` is removed.
+    ///
+    /// Fails if `name` is not a field-name, if `value` contains CR, LF, other control characters
+    /// (except HTAB), or non-ASCII characters, or if `value` can not be folded into lines of at
+    /// most 998 characters. Non-ASCII text must be encoded beforehand, e.g., using
+    /// [`encoded_word::encode`](crate::encoded_word::encode).
+    pub fn new<N, V>(name: N, value: V) -> Result<Self, InvalidRawField>
+    where
+        N: Into<String>,
+        V: AsRef<str>,
+    {
+        let name = name.into();
+        let value = value.as_ref().trim_matches(is_wsp).to_owned();
+
+        if !is_field_name(&name) {
+            return Err(InvalidRawField::Name);
+        }
+
+        if value.bytes().any(|b| b != b'\t' && !matches!(b, 32..=126)) {
+            return Err(InvalidRawField::Value);
+        }
+
+        let field = RawField { name, value };
+
+        if field
+            .lines()
+            .iter()
+            .any(|line| line.len() > Self::MAX_LINE_LENGTH)
+        {
+            return Err(InvalidRawField::LineTooLong);
+        }
+
+        Ok(field)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Serialize as "<name>: <value>" followed by CRLF.
+    ///
+    /// The value is folded by inserting CRLF before white space, such that lines do not exceed
+    /// 78 characters where possible. Unfolding restores the original value.
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for line in self.lines() {
+            writer.write_all(line.as_bytes())?;
+            writer.write_all(b"\r\n")?;
+        }
+
+        Ok(())
+    }
+
+    /// Split the field into lines. Every continuation line starts with white space.
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{}:", self.name)];
+
+        if self.value.is_empty() {
+            return lines;
+        }
+
+        lines[0].push(' ');
+
+        let bytes = self.value.as_bytes();
+
+        // Fold only before the first WSP of a run of WSP. Thus, no line consists of WSP only.
+        let boundaries = (1..bytes.len())
+            .filter(|&index| is_wsp(bytes[index] as char) && !is_wsp(bytes[index - 1] as char))
+            .chain(std::iter::once(bytes.len()));
+
+        let mut start = 0;
+        for end in boundaries {
+            let line = lines.last_mut().unwrap();
+
+            if start > 0 && line.len() + (end - start) > Self::RECOMMENDED_LINE_LENGTH {
+                lines.push(self.value[start..end].to_owned());
+            } else {
+                line.push_str(&self.value[start..end]);
+            }
+
+            start = end;
+        }
+
+        lines
+    }
+}
+
+/// Error returned by [`RawField::new`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidRawField {
+    /// The name is not a field-name.
+    Name,
+    /// The value contains CR, LF, other control characters, or non-ASCII characters.
+    Value,
+    /// The value contains a word that does not fit into a line of 998 characters.
+    LineTooLong,
+}
+
+impl fmt::Display for InvalidRawField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidRawField::Name => write!(f, "invalid field name"),
+            InvalidRawField::Value => {
+                write!(f, "field value contains control or non-ASCII characters")
+            }
+            InvalidRawField::LineTooLong => {
+                write!(
+                    f,
+                    "field can not be folded into lines of at most 998 characters"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidRawField {}
+
+/// WSP = SP / HTAB
+fn is_wsp(character: char) -> bool {
+    matches!(character, ' ' | '\t')
+}
+
+/// 3.4.  Address Specification
+///
+/// address = mailbox / group
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Address {
+    Mailbox(Mailbox),
+    Group(Group),
+}
+
+impl Address {
+    /// All mailboxes of this address, i.e., the mailbox itself or the members of the group.
+    pub fn mailboxes(&self) -> &[Mailbox] {
+        match self {
+            Address::Mailbox(mailbox) => std::slice::from_ref(mailbox),
+            Address::Group(group) => &group.mailboxes,
+        }
+    }
+}
+
+/// mailbox = name-addr / addr-spec
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mailbox {
+    /// Display name with comments removed, quoted-strings unquoted, encoded-words (RFC 2047)
+    /// decoded, and words separated by single spaces.
+    pub display_name: Option<String>,
+    pub addr_spec: AddrSpec,
+}
+
+/// group = display-name ":" [group-list] ";" [CFWS]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    pub display_name: String,
+    pub mailboxes: Vec<Mailbox>,
+}
+
+/// addr-spec = local-part "@" domain
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddrSpec {
+    /// Local part with comments removed and quoted-strings unquoted, e.g., `john doe` for
+    /// `"john doe"@example.com`.
+    pub local_part: String,
+    /// Domain with comments removed, e.g., "example.com" or "[192.0.2.1]".
+    pub domain: String,
+}
+
+impl AddrSpec {
+    /// Convert into an RFC 5321 Mailbox as used in [`Command::Mail`](crate::Command::Mail)
+    /// and [`Command::Rcpt`](crate::Command::Rcpt), e.g., `"john doe"@example.com`.
+    ///
+    /// The local part is quoted during serialization when it is not a Dot-string. The conversion
+    /// fails when the local part contains characters that can not be quoted, when the domain is
+    /// not a valid RFC 5321 domain, or when a domain literal is not a valid address literal.
+    pub fn to_envelope(&self) -> Result<crate::Mailbox, InvalidEnvelopeAddress> {
+        // Domain / address-literal
+        let domain_or_literal = match self
+            .domain
+            .strip_prefix('[')
+            .and_then(|domain| domain.strip_suffix(']'))
+        {
+            Some(literal) => DomainOrAddress::Address(
+                parse_address_literal(literal).ok_or(InvalidEnvelopeAddress::AddressLiteral)?,
+            ),
+            None => DomainOrAddress::Domain(
+                Domain::new(self.domain.as_str()).map_err(|_| InvalidEnvelopeAddress::Domain)?,
+            ),
+        };
+
+        crate::Mailbox::new(self.local_part.as_str(), domain_or_literal)
+            .map_err(|_| InvalidEnvelopeAddress::LocalPart)
+    }
+}
+
+/// Error returned by [`AddrSpec::to_envelope`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidEnvelopeAddress {
+    /// The local part contains characters that are not allowed in a Quoted-string.
+    LocalPart,
+    /// The domain is not a valid RFC 5321 Domain.
+    Domain,
+    /// The domain literal is not a valid RFC 5321 address-literal.
+    AddressLiteral,
+}
+
+impl fmt::Display for InvalidEnvelopeAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidEnvelopeAddress::LocalPart => {
+                write!(f, "local part can not be represented in SMTP")
+            }
+            InvalidEnvelopeAddress::Domain => write!(f, "domain is not a valid SMTP domain"),
+            InvalidEnvelopeAddress::AddressLiteral => {
+                write!(f, "domain literal is not a valid SMTP address literal")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidEnvelopeAddress {}
+
+/// 3.6.4.  Identification Fields
+///
+/// msg-id = [CFWS] "<" id-left "@" id-right ">" [CFWS]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "UncheckedMessageId")
+)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MessageId {
+    id_left: String,
+    id_right: String,
+}
+
+/// Deserialized fields of a [`MessageId`] that are validated by [`MessageId::new_obs`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedMessageId {
+    id_left: String,
+    id_right: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedMessageId> for MessageId {
+    type Error = InvalidMessageId;
+
+    fn try_from(value: UncheckedMessageId) -> Result<Self, Self::Error> {
+        MessageId::new_obs(value.id_left, value.id_right)
+    }
+}
+
+impl MessageId {
+    /// Create a Message-ID, e.g., `<id_left@id_right>`.
+    ///
+    /// id-left must be a dot-atom-text, id-right must be a dot-atom-text or a
+    /// no-fold-literal (as required for new messages).
+    pub fn new<L, R>(id_left: L, id_right: R) -> Result<Self, InvalidMessageId>
+    where
+        L: Into<String>,
+        R: Into<String>,
+    {
+        let id_left = id_left.into();
+        let id_right = id_right.into();
+
+        // no-fold-literal = "[" *dtext "]"
+        let is_no_fold_literal = |value: &str| {
+            value
+                .strip_prefix('[')
+                .and_then(|value| value.strip_suffix(']'))
+                .map(|value| value.is_empty() || is_address(value))
+                .unwrap_or(false)
+        };
+
+        match is_dot_atom_text(&id_left)
+            && (is_dot_atom_text(&id_right) || is_no_fold_literal(&id_right))
+        {
+            true => Ok(MessageId { id_left, id_right }),
+            false => Err(InvalidMessageId(())),
+        }
+    }
+
+    /// Create a Message-ID that may use the obsolete syntax, e.g., as found in received messages.
+    ///
+    /// obs-id-left = local-part
+    ///
+    /// obs-id-right = domain
+    ///
+    /// `id_left` is the content of the local-part, i.e., without CFWS and with quoted-strings
+    /// unquoted. `id_right` is the content of the domain, i.e., a dot-atom-text or a domain literal
+    /// without FWS. Fails if `id_left` contains control characters (except HTAB) or non-ASCII
+    /// characters, or if `id_right` is neither a dot-atom-text nor a domain literal.
+    pub fn new_obs<L, R>(id_left: L, id_right: R) -> Result<Self, InvalidMessageId>
+    where
+        L: Into<String>,
+        R: Into<String>,
+    {
+        let id_left = id_left.into();
+        let id_right = id_right.into();
+
+        // domain-literal = [CFWS] "[" *([FWS] dtext) [FWS] "]" [CFWS]
+        let is_domain_literal = |value: &str| {
+            value
+                .strip_prefix('[')
+                .and_then(|value| value.strip_suffix(']'))
+                .map(|value| {
+                    !value.contains(['[', ']']) && value.bytes().all(|b| matches!(b, 33..=126))
+                })
+                .unwrap_or(false)
+        };
+
+        match id_left.bytes().all(|b| b == b'\t' || matches!(b, 32..=126))
+            && (is_dot_atom_text(&id_right) || is_domain_literal(&id_right))
+        {
+            true => Ok(MessageId { id_left, id_right }),
+            false => Err(InvalidMessageId(())),
+        }
+    }
+
+    /// Content of id-left, i.e., without CFWS and with quoted-strings unquoted.
+    pub fn id_left(&self) -> &str {
+        &self.id_left
+    }
+
+    /// Content of id-right, e.g., "example.com" or "[192.0.2.1]".
+    pub fn id_right(&self) -> &str {
+        &self.id_right
+    }
+
+    /// Generate a unique Message-ID for `domain`.
+    ///
+    /// id-left is built from the current time, the process ID, a process-wide counter, and
+    /// random bits.
+    pub fn generate(domain: &str) -> Result<Self, InvalidMessageId> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        // `RandomState` is randomly seeded.
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(now);
+        hasher.write_u64(count);
+        let random = hasher.finish();
+
+        MessageId::new(
+            format!(
+                "{:x}.{:x}.{:x}.{:016x}",
+                now,
+                std::process::id(),
+                count,
+                random
+            ),
+            domain,
+        )
+    }
+
+    /// Serialize as, e.g., "<1234@example.com>".
+    ///
+    /// id-left is quoted if it is not a dot-atom-text (which is only possible with the obsolete
+    /// syntax).
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match is_dot_atom_text(&self.id_left) {
+            true => write!(writer, "<{}@{}>", self.id_left, self.id_right),
+            false => write!(
+                writer,
+                "<\"{}\"@{}>",
+                escape_quoted(&self.id_left),
+                self.id_right
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidMessageId(());
+
+impl fmt::Display for InvalidMessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid id-left or id-right")
+    }
+}
+
+impl std::error::Error for InvalidMessageId {}
+
+/// dot-atom-text = 1*atext *("." 1*atext)
+fn is_dot_atom_text(value: &str) -> bool {
+    value.split('.').all(is_atom)
+}
+
+/// 3.3.  Date and Time Specification
+///
+/// date-time = [ day-of-week "," ] date time [CFWS]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub day_of_week: Option<DayOfWeek>,
+    pub day: u8,
+    pub month: Month,
+    pub year: u32,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: Option<u8>,
+    /// Offset from Universal Time in minutes, e.g., `-420` for "-0700".
+    pub zone: i16,
+}
+
+impl DateTime {
+    /// Create a date-time from the number of seconds since the Unix epoch and the offset
+    /// from Universal Time in minutes.
+    ///
+    /// Fails if the (local) date is not within the years 1900 to 9999 or if the zone offset is
+    /// out of range.
+    pub fn from_timestamp(timestamp: i64, zone: i16) -> Result<DateTime, InvalidDateTime> {
+        // 1900-01-01 and 9999-12-31 in days since the Unix epoch
+        const MIN_DAYS: i64 = -25_567;
+        const MAX_DAYS: i64 = 2_932_896;
+
+        let local = timestamp
+            .checked_add(i64::from(zone) * 60)
+            .ok_or(InvalidDateTime::Year)?;
+        let days = local.div_euclid(86400);
+        let seconds = local.rem_euclid(86400);
+
+        if !(MIN_DAYS..=MAX_DAYS).contains(&days) {
+            return Err(InvalidDateTime::Year);
+        }
+
+        let (year, month, day) = civil_from_days(days);
+
+        let date_time = DateTime {
+            day_of_week: Some(DayOfWeek::from_days(days)),
+            day: day as u8,
+            month: Month::from_number(month as u8).unwrap(),
+            year: year as u32,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds % 3600 / 60) as u8,
+            second: Some((seconds % 60) as u8),
+            zone,
+        };
+
+        date_time.validate()?;
+
+        Ok(date_time)
+    }
+
+    /// Check that the date-time is a valid calendar date and time.
+    ///
+    /// The year must be within 1900 and 9999, the day must exist in the month, and the
+    /// day-of-week (if any) must match the date. A second of 60 is accepted for leap seconds.
+    pub fn validate(&self) -> Result<(), InvalidDateTime> {
+        if !(1900..=9999).contains(&self.year) {
+            return Err(InvalidDateTime::Year);
+        }
+
+        if self.day == 0 || self.day > self.month.days(self.year) {
+            return Err(InvalidDateTime::Day);
+        }
+
+        if self.hour > 23 {
+            return Err(InvalidDateTime::Hour);
+        }
+
+        if self.minute > 59 {
+            return Err(InvalidDateTime::Minute);
+        }
+
+        if matches!(self.second, Some(second) if second > 60) {
+            return Err(InvalidDateTime::Second);
+        }
+
+        if self.zone.unsigned_abs() >= 100 * 60 {
+            return Err(InvalidDateTime::Zone);
+        }
+
+        if let Some(day_of_week) = self.day_of_week {
+            let days = days_from_civil(
+                self.year as i64,
+                self.month.number() as i64,
+                self.day as i64,
+            );
+
+            if day_of_week != DayOfWeek::from_days(days) {
+                return Err(InvalidDateTime::DayOfWeek);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of seconds since the Unix epoch (1970-01-01 00:00:00 UTC).
+    pub fn timestamp(&self) -> i64 {
+        let days = days_from_civil(
+            self.year as i64,
+            self.month.number() as i64,
+            self.day as i64,
+        );
+        let seconds = self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second.unwrap_or_default() as i64;
+
+        days * 86400 + seconds - self.zone as i64 * 60
+    }
+
+    /// Serialize as, e.g., "Thu, 21 May 1998 05:33:29 -0700".
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        if let Some(day_of_week) = self.day_of_week {
+            write!(writer, "{}, ", day_of_week.name())?;
+        }
+
+        write!(
+            writer,
+            "{} {} {:04} {:02}:{:02}",
+            self.day,
+            self.month.name(),
+            self.year,
+            self.hour,
+            self.minute
+        )?;
+
+        if let Some(second) = self.second {
+            write!(writer, ":{:02}", second)?;
+        }
+
+        let sign = if self.zone < 0 { '-' } else { '+' };
+        let zone = self.zone.unsigned_abs();
+
+        write!(writer, " {}{:02}{:02}", sign, zone / 60, zone % 60)
+    }
+}
+
+/// Error returned by [`DateTime::validate`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidDateTime {
+    /// The year is before 1900 or after 9999.
+    Year,
+    /// The day does not exist in the month.
+    Day,
+    Hour,
+    Minute,
+    Second,
+    /// The zone offset is not representable as "+hhmm" or "-hhmm".
+    Zone,
+    /// The day-of-week does not match the date.
+    DayOfWeek,
+}
+
+impl fmt::Display for InvalidDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidDateTime::Year => write!(f, "year is not within 1900 and 9999"),
+            InvalidDateTime::Day => write!(f, "day does not exist in month"),
+            InvalidDateTime::Hour => write!(f, "hour is out of range"),
+            InvalidDateTime::Minute => write!(f, "minute is out of range"),
+            InvalidDateTime::Second => write!(f, "second is out of range"),
+            InvalidDateTime::Zone => write!(f, "zone is out of range"),
+            InvalidDateTime::DayOfWeek => write!(f, "day-of-week does not match date"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidDateTime {}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<DateTime> for chrono::DateTime<chrono::FixedOffset> {
+    type Error = InvalidDateTime;
+
+    fn try_from(value: DateTime) -> Result<Self, Self::Error> {
+        use chrono::{FixedOffset, NaiveDate, NaiveTime, TimeZone};
+
+        value.validate()?;
+
+        let date = NaiveDate::from_ymd_opt(
+            i32::try_from(value.year).map_err(|_| InvalidDateTime::Year)?,
+            value.month.number() as u32,
+            value.day as u32,
+        )
+        .ok_or(InvalidDateTime::Year)?;
+
+        // chrono represents a leap second as 59 seconds plus (at least) one second of nanos.
+        let time = match value.second {
+            Some(60) => {
+                NaiveTime::from_hms_milli_opt(value.hour as u32, value.minute as u32, 59, 1_000)
+            }
+            second => NaiveTime::from_hms_opt(
+                value.hour as u32,
+                value.minute as u32,
+                second.unwrap_or_default() as u32,
+            ),
+        }
+        .ok_or(InvalidDateTime::Second)?;
+
+        FixedOffset::east_opt(value.zone as i32 * 60)
+            .ok_or(InvalidDateTime::Zone)?
+            .from_local_datetime(&date.and_time(time))
+            .single()
+            .ok_or(InvalidDateTime::Zone)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::FixedOffset>> for DateTime {
+    type Error = InvalidDateTime;
+
+    /// Fractional seconds are truncated.
+    fn try_from(value: chrono::DateTime<chrono::FixedOffset>) -> Result<Self, Self::Error> {
+        use chrono::{Datelike, Offset, Timelike};
+
+        let offset = value.offset().fix().local_minus_utc();
+        if offset % 60 != 0 {
+            return Err(InvalidDateTime::Zone);
+        }
+
+        let second = match value.nanosecond() >= 1_000_000_000 {
+            true => 60,
+            false => value.second() as u8,
+        };
+
+        let date_time = DateTime {
+            day_of_week: Some(DayOfWeek::from_days(
+                value.date_naive().num_days_from_ce() as i64 - 719163,
+            )),
+            day: value.day() as u8,
+            month: Month::from_number(value.month() as u8).unwrap(),
+            year: u32::try_from(value.year()).map_err(|_| InvalidDateTime::Year)?,
+            hour: value.hour() as u8,
+            minute: value.minute() as u8,
+            second: Some(second),
+            zone: i16::try_from(offset / 60).map_err(|_| InvalidDateTime::Zone)?,
+        };
+
+        date_time.validate()?;
+
+        Ok(date_time)
+    }
+}
+
+/// day-name = "Mon" / "Tue" / "Wed" / "Thu" / "Fri" / "Sat" / "Sun"
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DayOfWeek {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl DayOfWeek {
+    /// Day-of-week of the given number of days since 1970-01-01 (a Thursday).
+    fn from_days(days: i64) -> DayOfWeek {
+        const DAYS: [DayOfWeek; 7] = [
+            DayOfWeek::Monday,
+            DayOfWeek::Tuesday,
+            DayOfWeek::Wednesday,
+            DayOfWeek::Thursday,
+            DayOfWeek::Friday,
+            DayOfWeek::Saturday,
+            DayOfWeek::Sunday,
+        ];
+
+        DAYS[(days + 3).rem_euclid(7) as usize]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DayOfWeek::Monday => "Mon",
+            DayOfWeek::Tuesday => "Tue",
+            DayOfWeek::Wednesday => "Wed",
+            DayOfWeek::Thursday => "Thu",
+            DayOfWeek::Friday => "Fri",
+            DayOfWeek::Saturday => "Sat",
+            DayOfWeek::Sunday => "Sun",
+        }
+    }
+}
+
+/// month = "Jan" / "Feb" / "Mar" / "Apr" / "May" / "Jun" / "Jul" / "Aug" / "Sep" / "Oct" / "Nov" / "Dec"
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum Month {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+impl Month {
+    /// Number of the month, starting with 1 for January.
+    pub fn number(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    /// Month with the given number, starting with 1 for January.
+    pub fn from_number(number: u8) -> Option<Month> {
+        const MONTHS: [Month; 12] = [
+            Month::January,
+            Month::February,
+            Month::March,
+            Month::April,
+            Month::May,
+            Month::June,
+            Month::July,
+            Month::August,
+            Month::September,
+            Month::October,
+            Month::November,
+            Month::December,
+        ];
+
+        MONTHS.get((number as usize).checked_sub(1)?).copied()
+    }
+
+    /// Number of days of the month in the given year.
+    pub fn days(&self, year: u32) -> u8 {
+        match self {
+            Month::February
+                if year.is_multiple_of(4)
+                    && (!year.is_multiple_of(100) || year.is_multiple_of(400)) =>
+            {
+                29
+            }
+            Month::February => 28,
+            Month::April | Month::June | Month::September | Month::November => 30,
+            _ => 31,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Month::January => "Jan",
+            Month::February => "Feb",
+            Month::March => "Mar",
+            Month::April => "Apr",
+            Month::May => "May",
+            Month::June => "Jun",
+            Month::July => "Jul",
+            Month::August => "Aug",
+            Month::September => "Sep",
+            Month::October => "Oct",
+            Month::November => "Nov",
+            Month::December => "Dec",
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Year, month, and day of the given number of days since 1970-01-01.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DateTime, DayOfWeek, InvalidDateTime, InvalidRawField, MessageId, Month, RawField,
+    };
+
+    const DATE_TIME: DateTime = DateTime {
+        day_of_week: Some(DayOfWeek::Thursday),
+        day: 21,
+        month: Month::May,
+        year: 1998,
+        hour: 5,
+        minute: 33,
+        second: Some(29),
+        zone: -420,
+    };
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(DATE_TIME.timestamp(), 895_754_009);
+        assert_eq!(DateTime::from_timestamp(895_754_009, -420), Ok(DATE_TIME));
+
+        for timestamp in [0, 951_782_400, 4_107_542_399] {
+            for zone in [-720, 0, 90] {
+                let date_time = DateTime::from_timestamp(timestamp, zone).unwrap();
+                assert_eq!(date_time.validate(), Ok(()));
+                assert_eq!(date_time.timestamp(), timestamp);
+            }
+        }
+
+        // 1900-01-01T00:00:00Z and 9999-12-31T23:59:59Z
+        for timestamp in [-2_208_988_800, 253_402_300_799] {
+            let date_time = DateTime::from_timestamp(timestamp, 0).unwrap();
+            assert_eq!(date_time.timestamp(), timestamp);
+        }
+
+        let tests = [
+            (-2_208_988_801, 0, InvalidDateTime::Year),
+            (253_402_300_800, 0, InvalidDateTime::Year),
+            (-2_208_988_800, -1, InvalidDateTime::Year),
+            (-70_000_000_000, 0, InvalidDateTime::Year),
+            (i64::MAX / 2, 0, InvalidDateTime::Year),
+            (i64::MAX, 60, InvalidDateTime::Year),
+            (i64::MIN, -60, InvalidDateTime::Year),
+            (0, 6000, InvalidDateTime::Zone),
+        ];
+
+        for (timestamp, zone, expected) in tests {
+            assert_eq!(DateTime::from_timestamp(timestamp, zone), Err(expected));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(DATE_TIME.validate(), Ok(()));
+
+        let tests = [
+            (
+                DateTime {
+                    year: 1899,
+                    day_of_week: None,
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Year,
+            ),
+            (
+                DateTime {
+                    year: 10000,
+                    day_of_week: None,
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Year,
+            ),
+            (
+                DateTime {
+                    day: 0,
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Day,
+            ),
+            (
+                DateTime {
+                    month: Month::February,
+                    day: 29,
+                    year: 1900,
+                    day_of_week: None,
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Day,
+            ),
+            (
+                DateTime {
+                    hour: 24,
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Hour,
+            ),
+            (
+                DateTime {
+                    minute: 60,
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Minute,
+            ),
+            (
+                DateTime {
+                    second: Some(61),
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Second,
+            ),
+            (
+                DateTime {
+                    zone: -6000,
+                    ..DATE_TIME
+                },
+                InvalidDateTime::Zone,
+            ),
+            (
+                DateTime {
+                    day_of_week: Some(DayOfWeek::Friday),
+                    ..DATE_TIME
+                },
+                InvalidDateTime::DayOfWeek,
+            ),
+        ];
+
+        for (date_time, expected) in tests {
+            assert_eq!(date_time.validate(), Err(expected));
+        }
+    }
+
+    #[test]
+    fn test_raw_field() {
+        let tests = [
+            ("X-Empty", "", "X-Empty:\r\n"),
+            ("Subject", "  Hello  World\t", "Subject: Hello  World\r\n"),
+            (
+                "Authentication-Results",
+                "mx.example.com; spf=pass smtp.mailfrom=example.net; dkim=pass header.d=example.net",
+                "Authentication-Results: mx.example.com; spf=pass smtp.mailfrom=example.net;\r\n dkim=pass header.d=example.net\r\n",
+            ),
+        ];
+
+        for (name, value, expected) in tests {
+            let mut out = Vec::new();
+            RawField::new(name, value)
+                .unwrap()
+                .serialize(&mut out)
+                .unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+
+        // Folding never produces lines consisting of WSP only.
+        let mut out = Vec::new();
+        RawField::new("X", format!("{}{}b", "a".repeat(80), " ".repeat(10)))
+            .unwrap()
+            .serialize(&mut out)
+            .unwrap();
+        let expected = format!("X: {}\r\n{}b\r\n", "a".repeat(80), " ".repeat(10));
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        assert_eq!(RawField::new("", "a"), Err(InvalidRawField::Name));
+        assert_eq!(RawField::new("X Y", "a"), Err(InvalidRawField::Name));
+        assert_eq!(RawField::new("X:", "a"), Err(InvalidRawField::Name));
+        assert_eq!(
+            RawField::new("X", "a\r\nBcc: injected@example.com"),
+            Err(InvalidRawField::Value)
+        );
+        assert_eq!(RawField::new("X", "a\nb"), Err(InvalidRawField::Value));
+        assert_eq!(RawField::new("X", "a\rb"), Err(InvalidRawField::Value));
+        assert_eq!(RawField::new("X", "a\x00b"), Err(InvalidRawField::Value));
+        assert_eq!(RawField::new("X", "a\x7fb"), Err(InvalidRawField::Value));
+        assert_eq!(
+            RawField::new("Subject", "Grüße"),
+            Err(InvalidRawField::Value)
+        );
+
+        assert!(RawField::new("X", "a".repeat(995)).is_ok());
+        assert_eq!(
+            RawField::new("X", "a".repeat(996)),
+            Err(InvalidRawField::LineTooLong)
+        );
+        assert!(RawField::new("X", format!("a {}", "a".repeat(997))).is_ok());
+        assert_eq!(
+            RawField::new("X", format!("a {}", "a".repeat(998))),
+            Err(InvalidRawField::LineTooLong)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_raw_field() {
+        let field: RawField = serde_json::from_str(r#"{"name":"X","value":"a b"}"#).unwrap();
+        assert_eq!(field, RawField::new("X", "a b").unwrap());
+        assert_eq!(
+            serde_json::to_string(&field).unwrap(),
+            r#"{"name":"X","value":"a b"}"#
+        );
+
+        assert!(
+            serde_json::from_str::<RawField>(r#"{"name":"X","value":"a\r\nBcc: evil@x"}"#).is_err()
+        );
+        assert!(serde_json::from_str::<RawField>(r#"{"name":"X:","value":"a"}"#).is_err());
+    }
+
+    #[test]
+    fn test_message_id() {
+        let message_id = MessageId::new("1234", "local.machine.example").unwrap();
+        let mut out = Vec::new();
+        message_id.serialize(&mut out).unwrap();
+        assert_eq!(out, b"<1234@local.machine.example>");
+
+        let message_id = MessageId::new_obs("a \"b\"", "example.com").unwrap();
+        assert_eq!(message_id.id_left(), "a \"b\"");
+        assert_eq!(message_id.id_right(), "example.com");
+        let mut out = Vec::new();
+        message_id.serialize(&mut out).unwrap();
+        assert_eq!(out, b"<\"a \\\"b\\\"\"@example.com>");
+
+        assert!(MessageId::new("a b", "example.com").is_err());
+        assert!(MessageId::new("a..b", "example.com").is_err());
+        assert!(MessageId::new("1234", "[a]b]").is_err());
+        assert!(MessageId::new("1234", "[]").is_ok());
+
+        assert!(MessageId::new_obs("a..b", "[a\\b]").is_ok());
+        for (id_left, id_right) in [
+            ("a\r\nBcc: evil@x", "example.com"),
+            ("a\u{e9}", "example.com"),
+            ("1234", "example.com\r\nBcc: evil@x"),
+            ("1234", "example com"),
+            ("1234", "[a]b]"),
+            ("1234", "[a\r\nb]"),
+            ("1234", ""),
+        ] {
+            assert!(MessageId::new_obs(id_left, id_right).is_err());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_message_id() {
+        let message_id = MessageId::new("1234", "example.com").unwrap();
+        let serialized = serde_json::to_string(&message_id).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MessageId>(&serialized).unwrap(),
+            message_id
+        );
+        assert!(serde_json::from_str::<MessageId>(
+            r#"{"id_left":"1234","id_right":"example.com>\r\nBcc: evil@x"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut out = Vec::new();
+        DATE_TIME.serialize(&mut out).unwrap();
+        assert_eq!(out, b"Thu, 21 May 1998 05:33:29 -0700");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono() {
+        let chrono = chrono::DateTime::<chrono::FixedOffset>::try_from(DATE_TIME).unwrap();
+        assert_eq!(chrono.timestamp(), DATE_TIME.timestamp());
+        assert_eq!(chrono.offset().local_minus_utc(), -420 * 60);
+        assert_eq!(DateTime::try_from(chrono), Ok(DATE_TIME));
+
+        let leap_second = DateTime {
+            day_of_week: Some(DayOfWeek::Saturday),
+            day: 31,
+            month: Month::December,
+            year: 2016,
+            hour: 23,
+            minute: 59,
+            second: Some(60),
+            zone: 0,
+        };
+        let chrono = chrono::DateTime::<chrono::FixedOffset>::try_from(leap_second).unwrap();
+        assert_eq!(DateTime::try_from(chrono), Ok(leap_second));
+    }
+}
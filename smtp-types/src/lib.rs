@@ -1,12 +1,27 @@
-use std::{borrow::Cow, fmt, io::Write, ops::Deref};
+use std::{
+    borrow::Cow,
+    fmt,
+    hash::{Hash, Hasher},
+    io::Write,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ops::Deref,
+};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::utils::escape_quoted;
+use crate::utils::{
+    escape_quoted, is_atom, is_domain, is_general_address_literal, is_quoted_content, is_utf8_atom,
+};
 
+pub mod auth_results;
+pub mod encoded_word;
+pub mod imf;
+pub mod trace;
 mod utils;
+pub mod xtext;
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Command {
     Ehlo {
@@ -16,11 +31,11 @@ pub enum Command {
         domain_or_address: DomainOrAddress,
     },
     Mail {
-        reverse_path: String,
+        reverse_path: ReversePath,
         parameters: Vec<Parameter>,
     },
     Rcpt {
-        forward_path: String,
+        forward_path: ForwardPath,
         parameters: Vec<Parameter>,
     },
     Data,
@@ -94,34 +109,677 @@ pub enum Command {
     AuthLogin(Option<String>),
     // AUTH PLAIN
     AuthPlain(Option<String>),
+    /// Transfer a chunk of `size` octets of message data [RFC3030]
+    ///
+    /// The chunk immediately follows the command line and is not part of the command. `last`
+    /// marks the final chunk of the message.
+    Bdat {
+        size: u64,
+        last: bool,
+    },
+}
+
+/// Domain = sub-domain *("." sub-domain)
+///
+/// sub-domain =/ U-label  ; RFC 6531
+///
+/// Labels are limited to 63 octets and the domain to 255 octets (see RFC 5321, Section 4.5.3.1).
+/// Internationalized domains may contain U-labels, e.g., "bücher.example". These are mapped and
+/// validated according to UTS #46 (IDNA 2008), and the limits apply to their A-label form.
+///
+/// Domains are compared in their (lowercase) A-label form, i.e., case-insensitively and such that
+/// "bücher.example" equals "xn--bcher-kva.example".
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+#[derive(Clone, Debug)]
+pub struct Domain {
+    domain: String,
+    /// Lowercase A-label form used for comparison.
+    normalized: String,
+}
+
+impl Domain {
+    pub fn new<D>(domain: D) -> Result<Self, InvalidDomain>
+    where
+        D: Into<String>,
+    {
+        let domain = domain.into();
+
+        let (domain, normalized) = if domain.is_ascii() {
+            let normalized = domain.to_ascii_lowercase();
+            (domain, normalized)
+        } else {
+            // UTS #46 maps the input, e.g., to lowercase and NFC. Keep the mapped U-labels.
+            let normalized =
+                idna::domain_to_ascii_strict(&domain).map_err(|_| InvalidDomain(()))?;
+            match idna::domain_to_unicode(&normalized) {
+                (domain, Ok(())) => (domain, normalized),
+                (_, Err(_)) => return Err(InvalidDomain(())),
+            }
+        };
+
+        match is_domain(&normalized) {
+            true => Ok(Domain { domain, normalized }),
+            false => Err(InvalidDomain(())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.domain
+    }
+
+    /// Does the domain consist of ASCII labels only (including A-labels)?
+    pub fn is_ascii(&self) -> bool {
+        self.domain.is_ascii()
+    }
+
+    /// Convert all U-labels to A-labels, e.g., "bücher.example" to "xn--bcher-kva.example".
+    ///
+    /// Use this form when talking to a peer that does not support SMTPUTF8.
+    pub fn to_ascii(&self) -> Domain {
+        match self.is_ascii() {
+            true => self.clone(),
+            false => Domain {
+                domain: self.normalized.clone(),
+                normalized: self.normalized.clone(),
+            },
+        }
+    }
+
+    /// Convert all A-labels to U-labels, e.g., "xn--bcher-kva.example" to "bücher.example".
+    ///
+    /// The domain is returned unchanged if it contains no or an invalid A-label.
+    pub fn to_unicode(&self) -> Domain {
+        if !self
+            .normalized
+            .split('.')
+            .any(|label| label.starts_with("xn--"))
+        {
+            return self.clone();
+        }
+
+        match idna::domain_to_unicode(&self.normalized) {
+            (domain, Ok(())) => Domain {
+                domain,
+                normalized: self.normalized.clone(),
+            },
+            (_, Err(_)) => self.clone(),
+        }
+    }
+}
+
+impl PartialEq for Domain {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized == other.normalized
+    }
+}
+
+impl Eq for Domain {}
+
+impl Hash for Domain {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized.hash(state);
+    }
+}
+
+impl Deref for Domain {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.domain
+    }
+}
+
+impl fmt::Display for Domain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.domain)
+    }
+}
+
+impl TryFrom<String> for Domain {
+    type Error = InvalidDomain;
+
+    fn try_from(domain: String) -> Result<Self, Self::Error> {
+        Domain::new(domain)
+    }
 }
 
+impl TryFrom<&str> for Domain {
+    type Error = InvalidDomain;
+
+    fn try_from(domain: &str) -> Result<Self, Self::Error> {
+        Domain::new(domain)
+    }
+}
+
+impl From<Domain> for String {
+    fn from(domain: Domain) -> Self {
+        domain.domain
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DomainOrAddress {
-    Domain(String),
-    Address(String),
+    Domain(Domain),
+    Address(AddressLiteral),
 }
 
 impl DomainOrAddress {
+    /// Convert all U-labels of the domain to A-labels, see [`Domain::to_ascii`].
+    pub fn to_ascii(&self) -> DomainOrAddress {
+        match self {
+            DomainOrAddress::Domain(domain) => DomainOrAddress::Domain(domain.to_ascii()),
+            DomainOrAddress::Address(address) => DomainOrAddress::Address(address.clone()),
+        }
+    }
+
+    /// Serialize as used in "HELO" and "EHLO", i.e., U-labels are written as A-labels.
+    ///
+    /// "HELO" and "EHLO" are sent before SMTPUTF8 is negotiated.
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        write!(writer, "{}", self.to_ascii())
+    }
+}
+
+impl fmt::Display for DomainOrAddress {
+    /// Formats the domain or the address literal (with brackets), e.g., "[192.0.2.1]".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DomainOrAddress::Domain(domain) => write!(f, "{}", domain),
+            DomainOrAddress::Address(address) => write!(f, "[{}]", address),
+        }
+    }
+}
+
+impl From<Domain> for DomainOrAddress {
+    fn from(domain: Domain) -> Self {
+        DomainOrAddress::Domain(domain)
+    }
+}
+
+impl From<AddressLiteral> for DomainOrAddress {
+    fn from(address: AddressLiteral) -> Self {
+        DomainOrAddress::Address(address)
+    }
+}
+
+/// address-literal = "[" ( IPv4-address-literal /
+///                   IPv6-address-literal /
+///                   General-address-literal ) "]"
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AddressLiteral {
+    /// IPv4-address-literal = Snum 3("."  Snum)
+    V4(Ipv4Addr),
+    /// IPv6-address-literal = "IPv6:" IPv6-addr
+    V6(Ipv6Addr),
+    /// General-address-literal = Standardized-tag ":" 1*dcontent
+    General(GeneralAddressLiteral),
+}
+
+impl AddressLiteral {
+    /// IP address of this address literal (if it is an IPv4 or IPv6 address literal).
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self {
+            AddressLiteral::V4(address) => Some(IpAddr::V4(*address)),
+            AddressLiteral::V6(address) => Some(IpAddr::V6(*address)),
+            AddressLiteral::General(_) => None,
+        }
+    }
+
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        write!(writer, "[{}]", self)
+    }
+}
+
+impl fmt::Display for AddressLiteral {
+    /// Formats the address literal without brackets, e.g., "IPv6:2001:db8::1".
+    ///
+    /// IPv6 addresses are formatted canonically, i.e., as recommended by RFC 5952.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressLiteral::V4(address) => write!(f, "{}", address),
+            AddressLiteral::V6(address) => write!(f, "IPv6:{}", address),
+            AddressLiteral::General(general) => write!(f, "{}", general),
+        }
+    }
+}
+
+/// General-address-literal = Standardized-tag ":" 1*dcontent
+///
+/// Standardized-tag = Ldh-str
+///
+/// dcontent = %d33-90 / %d94-126
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "UncheckedGeneralAddressLiteral")
+)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GeneralAddressLiteral {
+    tag: String,
+    content: String,
+}
+
+/// Deserialized fields of a [`GeneralAddressLiteral`] that are validated by
+/// [`GeneralAddressLiteral::new`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedGeneralAddressLiteral {
+    tag: String,
+    content: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedGeneralAddressLiteral> for GeneralAddressLiteral {
+    type Error = InvalidAddressLiteral;
+
+    fn try_from(value: UncheckedGeneralAddressLiteral) -> Result<Self, Self::Error> {
+        GeneralAddressLiteral::new(value.tag, value.content)
+    }
+}
+
+impl GeneralAddressLiteral {
+    /// Create a General-address-literal, e.g., `GeneralAddressLiteral::new("x-tag", "content")`.
+    ///
+    /// Fails when `tag` is not an Ldh-str, when `tag` is "IPv6" (use [`AddressLiteral::V6`]), or
+    /// when `content` is empty or contains characters other than dcontent.
+    pub fn new<T, C>(tag: T, content: C) -> Result<Self, InvalidAddressLiteral>
+    where
+        T: Into<String>,
+        C: Into<String>,
+    {
+        let tag = tag.into();
+        let content = content.into();
+
+        match is_general_address_literal(&tag, &content) {
+            true => Ok(GeneralAddressLiteral { tag, content }),
+            false => Err(InvalidAddressLiteral(())),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+impl fmt::Display for GeneralAddressLiteral {
+    /// Formats the address literal without brackets, e.g., "x-tag:content".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.tag, self.content)
+    }
+}
+
+impl From<IpAddr> for AddressLiteral {
+    fn from(address: IpAddr) -> Self {
+        match address {
+            IpAddr::V4(address) => AddressLiteral::V4(address),
+            IpAddr::V6(address) => AddressLiteral::V6(address),
+        }
+    }
+}
+
+impl From<Ipv4Addr> for AddressLiteral {
+    fn from(address: Ipv4Addr) -> Self {
+        AddressLiteral::V4(address)
+    }
+}
+
+impl From<Ipv6Addr> for AddressLiteral {
+    fn from(address: Ipv6Addr) -> Self {
+        AddressLiteral::V6(address)
+    }
+}
+
+/// Reverse-path = Path / "<>"
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReversePath {
+    /// The null reverse-path "<>", e.g., used for delivery status notifications.
+    Null,
+    Path(Path),
+}
+
+impl ReversePath {
+    /// Is this the null reverse-path "<>"?
+    pub fn is_null(&self) -> bool {
+        matches!(self, ReversePath::Null)
+    }
+
+    /// See [`Mailbox::requires_smtputf8`].
+    pub fn requires_smtputf8(&self) -> bool {
+        match self {
+            ReversePath::Null => false,
+            ReversePath::Path(path) => path.requires_smtputf8(),
+        }
+    }
+
+    /// Convert all U-labels to A-labels, see [`Path::to_ascii`].
+    pub fn to_ascii(&self) -> ReversePath {
+        match self {
+            ReversePath::Null => ReversePath::Null,
+            ReversePath::Path(path) => ReversePath::Path(path.to_ascii()),
+        }
+    }
+
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            ReversePath::Null => writer.write_all(b"<>"),
+            ReversePath::Path(path) => path.serialize(writer),
+        }
+    }
+}
+
+/// Forward-path = Path
+///
+/// Note: "RCPT TO:" additionally accepts "<Postmaster@" Domain ">" and "<Postmaster>".
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ForwardPath {
+    /// "<Postmaster>"
+    Postmaster,
+    /// "<Postmaster@" Domain ">"
+    PostmasterAt(Domain),
+    Path(Path),
+}
+
+impl ForwardPath {
+    /// See [`Mailbox::requires_smtputf8`].
+    pub fn requires_smtputf8(&self) -> bool {
+        match self {
+            ForwardPath::Postmaster => false,
+            ForwardPath::PostmasterAt(domain) => !domain.is_ascii(),
+            ForwardPath::Path(path) => path.requires_smtputf8(),
+        }
+    }
+
+    /// Convert all U-labels to A-labels, see [`Path::to_ascii`].
+    pub fn to_ascii(&self) -> ForwardPath {
+        match self {
+            ForwardPath::Postmaster => ForwardPath::Postmaster,
+            ForwardPath::PostmasterAt(domain) => ForwardPath::PostmasterAt(domain.to_ascii()),
+            ForwardPath::Path(path) => ForwardPath::Path(path.to_ascii()),
+        }
+    }
+
     pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
         match self {
-            DomainOrAddress::Domain(domain) => write!(writer, "{}", domain),
-            DomainOrAddress::Address(address) => write!(writer, "[{}]", address),
+            ForwardPath::Postmaster => writer.write_all(b"<Postmaster>"),
+            ForwardPath::PostmasterAt(domain) => write!(writer, "<Postmaster@{}>", domain),
+            ForwardPath::Path(path) => path.serialize(writer),
+        }
+    }
+}
+
+/// Path = "<" [ A-d-l ":" ] Mailbox ">"
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    /// Domains of the source route (without "@").
+    ///
+    /// A-d-l = At-domain *( "," At-domain )
+    ///          ; Note that this form, the so-called "source
+    ///          ; route", MUST BE accepted, SHOULD NOT be
+    ///          ; generated, and SHOULD be ignored.
+    pub source_route: Vec<Domain>,
+    pub mailbox: Mailbox,
+}
+
+impl Path {
+    /// See [`Mailbox::requires_smtputf8`].
+    pub fn requires_smtputf8(&self) -> bool {
+        self.mailbox.requires_smtputf8()
+            || self.source_route.iter().any(|domain| !domain.is_ascii())
+    }
+
+    /// Convert all U-labels of the source route and the mailbox to A-labels, see
+    /// [`Mailbox::to_ascii`].
+    pub fn to_ascii(&self) -> Path {
+        Path {
+            source_route: self.source_route.iter().map(Domain::to_ascii).collect(),
+            mailbox: self.mailbox.to_ascii(),
+        }
+    }
+
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        write!(writer, "<{}>", self)
+    }
+}
+
+impl fmt::Display for Path {
+    /// Formats the path without angle brackets, e.g., `@a.org,@b.org:user@example.com`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.source_route.is_empty() {
+            let at_domains: Vec<_> = self
+                .source_route
+                .iter()
+                .map(|domain| format!("@{}", domain))
+                .collect();
+            write!(f, "{}:", at_domains.join(","))?;
+        }
+
+        write!(f, "{}", self.mailbox)
+    }
+}
+
+impl From<Mailbox> for Path {
+    fn from(mailbox: Mailbox) -> Self {
+        Path {
+            source_route: vec![],
+            mailbox,
         }
     }
 }
 
+/// Mailbox = Local-part "@" ( Domain / address-literal )
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "UncheckedMailbox")
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mailbox {
+    local_part: String,
+    domain_or_literal: DomainOrAddress,
+}
+
+/// Deserialized fields of a [`Mailbox`] that are validated by [`Mailbox::new`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedMailbox {
+    local_part: String,
+    domain_or_literal: DomainOrAddress,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedMailbox> for Mailbox {
+    type Error = InvalidMailbox;
+
+    fn try_from(value: UncheckedMailbox) -> Result<Self, Self::Error> {
+        Mailbox::new(value.local_part, value.domain_or_literal)
+    }
+}
+
+impl Mailbox {
+    /// Create a mailbox, e.g., `Mailbox::new("john", Domain::new("example.com")?.into())`.
+    ///
+    /// Fails if the local part contains characters that can not be represented in a
+    /// Quoted-string, e.g., CR or LF.
+    ///
+    /// The local part may contain non-ASCII characters. Such a mailbox can only be used with
+    /// SMTPUTF8, see [`Mailbox::requires_smtputf8`].
+    pub fn new<L>(local_part: L, domain_or_literal: DomainOrAddress) -> Result<Self, InvalidMailbox>
+    where
+        L: Into<String>,
+    {
+        let local_part = local_part.into();
+
+        match is_quoted_content(&local_part) {
+            true => Ok(Mailbox {
+                local_part,
+                domain_or_literal,
+            }),
+            false => Err(InvalidMailbox(())),
+        }
+    }
+
+    /// Local-part with a Quoted-string unquoted, e.g., `a@b` for `"a@b"@example.com`.
+    pub fn local_part(&self) -> &str {
+        &self.local_part
+    }
+
+    pub fn domain_or_literal(&self) -> &DomainOrAddress {
+        &self.domain_or_literal
+    }
+
+    /// Does this mailbox contain a non-ASCII local part or U-label, i.e., can it only be
+    /// transmitted when the server supports SMTPUTF8 (RFC 6531)?
+    pub fn requires_smtputf8(&self) -> bool {
+        !self.local_part.is_ascii()
+            || matches!(&self.domain_or_literal, DomainOrAddress::Domain(domain) if !domain.is_ascii())
+    }
+
+    /// Convert all U-labels of the domain to A-labels, see [`Domain::to_ascii`].
+    ///
+    /// The local part is kept as is. Thus, the mailbox still requires SMTPUTF8 when the local
+    /// part contains non-ASCII characters.
+    pub fn to_ascii(&self) -> Mailbox {
+        Mailbox {
+            local_part: self.local_part.clone(),
+            domain_or_literal: self.domain_or_literal.to_ascii(),
+        }
+    }
+}
+
+impl fmt::Display for Mailbox {
+    /// Formats the mailbox as it appears in a Path, e.g., `"a@b"@example.com`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Local-part = Dot-string / Quoted-string
+        match self.local_part.split('.').all(is_utf8_atom) {
+            true => write!(f, "{}@", self.local_part)?,
+            false => write!(f, "\"{}\"@", escape_quoted(&self.local_part))?,
+        }
+
+        write!(f, "{}", self.domain_or_literal)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum Parameter {
     /// Message size declaration [RFC1870]
     Size(u32),
+    /// Internationalized email, i.e., the message and envelope may contain UTF-8 [RFC6531]
+    SmtpUtf8,
+    /// Return the full message or only the headers in a DSN [RFC3461]
+    Ret(Ret),
+    /// Envelope identifier returned in a DSN [RFC3461]
+    ///
+    /// The value is stored decoded and encoded as xtext during serialization.
+    EnvId(String),
+    /// Conditions under which a DSN is generated [RFC3461]
+    Notify(Notify),
+    /// Original recipient [RFC3461]
+    Orcpt(Orcpt),
+    /// Body type of the message [RFC6152, RFC3030]
+    Body(BodyType),
     Other {
         keyword: String,
         value: Option<String>,
     },
 }
 
+/// Body type of a message, see [`Parameter::Body`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BodyType {
+    /// 7bit US-ASCII [RFC6152]
+    SevenBit,
+    /// 8bit MIME content [RFC6152]
+    EightBitMime,
+    /// Binary MIME content [RFC3030]
+    BinaryMime,
+}
+
+/// Content of a DSN, see [`Parameter::Ret`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Ret {
+    /// Return the full message
+    Full,
+    /// Return only the headers of the message
+    Hdrs,
+}
+
+/// Conditions under which a DSN is generated, see [`Parameter::Notify`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Notify {
+    /// Never generate a DSN
+    Never,
+    /// Generate a DSN when any of the conditions occurs
+    On(NotifyConditions),
+}
+
+/// Non-empty list of [`NotifyOn`] conditions without duplicates, see [`Notify::On`]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "Vec<NotifyOn>", into = "Vec<NotifyOn>")
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotifyConditions(Vec<NotifyOn>);
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NotifyOn {
+    Success,
+    Failure,
+    Delay,
+}
+
+/// Original recipient, see [`Parameter::Orcpt`]
+///
+/// The address is stored decoded and encoded during serialization, i.e., as utf-8-addr-xtext
+/// when the address type is "utf-8" [RFC6533] and as xtext otherwise.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "UncheckedOrcpt")
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Orcpt {
+    addr_type: String,
+    address: String,
+}
+
+/// Deserialized fields of an [`Orcpt`] that are validated by [`Orcpt::new`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedOrcpt {
+    addr_type: String,
+    address: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedOrcpt> for Orcpt {
+    type Error = InvalidOrcpt;
+
+    fn try_from(value: UncheckedOrcpt) -> Result<Self, Self::Error> {
+        Orcpt::new(value.addr_type, value.address)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AtomOrQuoted {
     Atom(String),
@@ -148,6 +806,54 @@ impl Command {
             Command::AuthLogin(_) => "AUTHLOGIN",
             // TODO: SMTP AUTH PLAIN
             Command::AuthPlain(_) => "AUTHPLAIN",
+            Command::Bdat { .. } => "BDAT",
+        }
+    }
+
+    /// Can this command only be sent when the server advertises [`Capability::SMTPUTF8`]?
+    ///
+    /// This is the case when a path contains a non-ASCII local part or a U-label, when a parameter
+    /// requires it (see [`Parameter::requires_smtputf8`]), or when an argument contains non-ASCII
+    /// characters. A client should check this for all commands of an envelope before sending "MAIL".
+    ///
+    /// Note: "HELO" and "EHLO" never require SMTPUTF8 because their domain is sent as A-label.
+    pub fn requires_smtputf8(&self) -> bool {
+        match self {
+            Command::Ehlo { .. } | Command::Helo { .. } => false,
+            Command::Mail {
+                reverse_path,
+                parameters,
+            } => {
+                reverse_path.requires_smtputf8()
+                    || parameters.iter().any(Parameter::requires_smtputf8)
+            }
+            Command::Rcpt {
+                forward_path,
+                parameters,
+            } => {
+                forward_path.requires_smtputf8()
+                    || parameters.iter().any(Parameter::requires_smtputf8)
+            }
+            Command::Vrfy {
+                user_or_mailbox: argument,
+            }
+            | Command::Expn {
+                mailing_list: argument,
+            }
+            | Command::Help {
+                argument: Some(argument),
+            }
+            | Command::Noop {
+                argument: Some(argument),
+            } => !argument.is_ascii(),
+            Command::Help { argument: None } | Command::Noop { argument: None } => false,
+            Command::Data
+            | Command::Rset
+            | Command::Quit
+            | Command::StartTLS
+            | Command::AuthLogin(_)
+            | Command::AuthPlain(_)
+            | Command::Bdat { .. } => false,
         }
     }
 
@@ -170,9 +876,8 @@ impl Command {
                 reverse_path,
                 parameters,
             } => {
-                writer.write_all(b"MAIL FROM:<")?;
-                writer.write_all(reverse_path.as_bytes())?;
-                writer.write_all(b">")?;
+                writer.write_all(b"MAIL FROM:")?;
+                reverse_path.serialize(writer)?;
 
                 for parameter in parameters {
                     writer.write_all(b" ")?;
@@ -184,9 +889,8 @@ impl Command {
                 forward_path,
                 parameters,
             } => {
-                writer.write_all(b"RCPT TO:<")?;
-                writer.write_all(forward_path.as_bytes())?;
-                writer.write_all(b">")?;
+                writer.write_all(b"RCPT TO:")?;
+                forward_path.serialize(writer)?;
 
                 for parameter in parameters {
                     writer.write_all(b" ")?;
@@ -244,6 +948,14 @@ impl Command {
                 writer.write_all(b"AUTH PLAIN ")?;
                 writer.write_all(data.as_bytes())?;
             }
+            // bdat-cmd = "BDAT" SP chunk-size [ SP end-marker ] CR LF
+            Bdat { size, last } => {
+                write!(writer, "BDAT {}", size)?;
+
+                if *last {
+                    writer.write_all(b" LAST")?;
+                }
+            }
         }
 
         write!(writer, "\r\n")
@@ -251,11 +963,75 @@ impl Command {
 }
 
 impl Parameter {
+    pub fn keyword(&self) -> &str {
+        match self {
+            Parameter::Size(_) => "SIZE",
+            Parameter::SmtpUtf8 => "SMTPUTF8",
+            Parameter::Ret(_) => "RET",
+            Parameter::EnvId(_) => "ENVID",
+            Parameter::Notify(_) => "NOTIFY",
+            Parameter::Orcpt(_) => "ORCPT",
+            Parameter::Body(_) => "BODY",
+            Parameter::Other { keyword, .. } => keyword,
+        }
+    }
+
+    /// Is this a DSN parameter, i.e., may it only be used when the server advertises
+    /// [`Capability::DSN`]?
+    pub fn is_dsn(&self) -> bool {
+        matches!(
+            self,
+            Parameter::Ret(_) | Parameter::EnvId(_) | Parameter::Notify(_) | Parameter::Orcpt(_)
+        )
+    }
+
+    /// Can this parameter only be used when the server advertises [`Capability::SMTPUTF8`]?
+    ///
+    /// This is the case for "SMTPUTF8" itself and for unknown parameters with a non-ASCII value.
+    /// The DSN parameters are always encoded as ASCII.
+    pub fn requires_smtputf8(&self) -> bool {
+        match self {
+            Parameter::SmtpUtf8 => true,
+            Parameter::Other {
+                value: Some(value), ..
+            } => !value.is_ascii(),
+            Parameter::Size(_)
+            | Parameter::Ret(_)
+            | Parameter::EnvId(_)
+            | Parameter::Notify(_)
+            | Parameter::Orcpt(_)
+            | Parameter::Body(_)
+            | Parameter::Other { value: None, .. } => false,
+        }
+    }
+
     pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
         match self {
             Parameter::Size(size) => {
                 write!(writer, "SIZE={}", size)?;
             }
+            Parameter::SmtpUtf8 => {
+                writer.write_all(b"SMTPUTF8")?;
+            }
+            Parameter::Ret(ret) => {
+                writer.write_all(b"RET=")?;
+                ret.serialize(writer)?;
+            }
+            Parameter::EnvId(envid) => {
+                write!(writer, "ENVID={}", xtext::encode(envid))?;
+            }
+            Parameter::Notify(notify) => {
+                writer.write_all(b"NOTIFY=")?;
+                notify.serialize(writer)?;
+            }
+            Parameter::Orcpt(orcpt) => {
+                writer.write_all(b"ORCPT=")?;
+                orcpt.serialize(writer)?;
+            }
+            Parameter::Body(body_type) => {
+                writer.write_all(b"BODY=")?;
+                body_type.serialize(writer)?;
+            }
             Parameter::Other { keyword, value } => {
                 writer.write_all(keyword.as_bytes())?;
 
@@ -270,7 +1046,171 @@ impl Parameter {
     }
 }
 
+impl BodyType {
+    /// Must the message be transferred with "BDAT" instead of "DATA"?
+    ///
+    /// This is the case for "BINARYMIME" [RFC3030].
+    pub fn requires_bdat(&self) -> bool {
+        matches!(self, BodyType::BinaryMime)
+    }
+
+    /// Can this body type be used with a server that advertises `capabilities`?
+    ///
+    /// "8BITMIME" requires [`Capability::EightBitMIME`] and "BINARYMIME" requires
+    /// [`Capability::BinaryMIME`] as well as [`Capability::Chunking`] (for "BDAT").
+    pub fn is_supported(&self, capabilities: &[Capability]) -> bool {
+        match self {
+            BodyType::SevenBit => true,
+            BodyType::EightBitMime => capabilities.contains(&Capability::EightBitMIME),
+            BodyType::BinaryMime => {
+                capabilities.contains(&Capability::BinaryMIME)
+                    && capabilities.contains(&Capability::Chunking)
+            }
+        }
+    }
+
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            BodyType::SevenBit => writer.write_all(b"7BIT"),
+            BodyType::EightBitMime => writer.write_all(b"8BITMIME"),
+            BodyType::BinaryMime => writer.write_all(b"BINARYMIME"),
+        }
+    }
+}
+
+impl Ret {
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            Ret::Full => writer.write_all(b"FULL"),
+            Ret::Hdrs => writer.write_all(b"HDRS"),
+        }
+    }
+}
+
+impl Notify {
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            Notify::Never => writer.write_all(b"NEVER"),
+            Notify::On(conditions) => conditions.serialize(writer),
+        }
+    }
+}
+
+impl NotifyConditions {
+    /// Fails when `conditions` is empty or contains a condition more than once.
+    pub fn new(conditions: Vec<NotifyOn>) -> Result<Self, InvalidNotifyConditions> {
+        let unique = conditions
+            .iter()
+            .enumerate()
+            .all(|(index, condition)| !conditions[..index].contains(condition));
+
+        match !conditions.is_empty() && unique {
+            true => Ok(NotifyConditions(conditions)),
+            false => Err(InvalidNotifyConditions(())),
+        }
+    }
+
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let (head, tail) = self.0.split_first().expect("conditions are not empty");
+
+        head.serialize(writer)?;
+
+        for condition in tail {
+            writer.write_all(b",")?;
+            condition.serialize(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Deref for NotifyConditions {
+    type Target = [NotifyOn];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<NotifyOn>> for NotifyConditions {
+    type Error = InvalidNotifyConditions;
+
+    fn try_from(conditions: Vec<NotifyOn>) -> Result<Self, Self::Error> {
+        NotifyConditions::new(conditions)
+    }
+}
+
+impl From<NotifyConditions> for Vec<NotifyOn> {
+    fn from(conditions: NotifyConditions) -> Self {
+        conditions.0
+    }
+}
+
+impl NotifyOn {
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            NotifyOn::Success => writer.write_all(b"SUCCESS"),
+            NotifyOn::Failure => writer.write_all(b"FAILURE"),
+            NotifyOn::Delay => writer.write_all(b"DELAY"),
+        }
+    }
+}
+
+impl Orcpt {
+    /// Create an original recipient from its address type, e.g., "rfc822" or "utf-8", and its
+    /// decoded address.
+    ///
+    /// addr-type = atom
+    ///
+    /// Fails when `addr_type` is not an atom or when the address of the "utf-8" address type
+    /// cannot be encoded, see [`xtext::encode_utf8_addr_xtext`].
+    pub fn new<T, A>(addr_type: T, address: A) -> Result<Self, InvalidOrcpt>
+    where
+        T: Into<String>,
+        A: Into<String>,
+    {
+        let addr_type = addr_type.into();
+        let address = address.into();
+
+        let valid = is_atom(&addr_type)
+            && (!addr_type.eq_ignore_ascii_case("utf-8")
+                || xtext::encode_utf8_addr_xtext(&address).is_ok());
+
+        match valid {
+            true => Ok(Orcpt { addr_type, address }),
+            false => Err(InvalidOrcpt(())),
+        }
+    }
+
+    pub fn addr_type(&self) -> &str {
+        &self.addr_type
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        // The "utf-8" address type uses utf-8-addr-xtext instead of xtext [RFC6533]
+        let address = match self.addr_type.eq_ignore_ascii_case("utf-8") {
+            true => Cow::Owned(
+                xtext::encode_utf8_addr_xtext(&self.address)
+                    .expect("address was validated in Orcpt::new"),
+            ),
+            false => xtext::encode(&self.address),
+        };
+
+        write!(writer, "{};{}", self.addr_type, address)
+    }
+}
+
 impl AtomOrQuoted {
+    pub fn is_ascii(&self) -> bool {
+        match self {
+            AtomOrQuoted::Atom(value) | AtomOrQuoted::Quoted(value) => value.is_ascii(),
+        }
+    }
+
     pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
         match self {
             AtomOrQuoted::Atom(atom) => {
@@ -294,11 +1234,12 @@ impl AtomOrQuoted {
 #[non_exhaustive]
 pub enum Response {
     Greeting {
-        domain: String,
+        /// Greeting = "220 " (Domain / address-literal) ...
+        domain: DomainOrAddress,
         text: String,
     },
     Ehlo {
-        domain: String,
+        domain: Domain,
         greet: Option<String>,
         capabilities: Vec<Capability>,
     },
@@ -311,7 +1252,7 @@ pub enum Response {
 impl Response {
     pub fn greeting<D, T>(domain: D, text: T) -> Response
     where
-        D: Into<String>,
+        D: Into<DomainOrAddress>,
         T: Into<String>,
     {
         Response::Greeting {
@@ -320,13 +1261,12 @@ impl Response {
         }
     }
 
-    pub fn ehlo<D, G>(domain: D, greet: Option<G>, capabilities: Vec<Capability>) -> Response
+    pub fn ehlo<G>(domain: Domain, greet: Option<G>, capabilities: Vec<Capability>) -> Response
     where
-        D: Into<String>,
         G: Into<String>,
     {
         Response::Ehlo {
-            domain: domain.into(),
+            domain,
             greet: greet.map(Into::into),
             capabilities,
         }
@@ -345,6 +1285,8 @@ impl Response {
     pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
         match self {
             Response::Greeting { domain, text } => {
+                // The greeting is sent before SMTPUTF8 is negotiated.
+                let domain = domain.to_ascii();
                 let lines = text.lines().collect::<Vec<_>>();
 
                 if let Some((first, tail)) = lines.split_first() {
@@ -368,6 +1310,7 @@ impl Response {
                 greet,
                 capabilities,
             } => {
+                let domain = domain.to_ascii();
                 let greet = match greet {
                     Some(greet) => format!(" {}", greet),
                     None => "".to_string(),
@@ -828,6 +1771,64 @@ impl fmt::Display for TextString<'_> {
     }
 }
 
+#[derive(Debug)]
+pub struct InvalidDomain(());
+
+impl fmt::Display for InvalidDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid domain")
+    }
+}
+
+impl std::error::Error for InvalidDomain {}
+
+#[derive(Debug)]
+pub struct InvalidAddressLiteral(());
+
+impl fmt::Display for InvalidAddressLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid general address literal")
+    }
+}
+
+impl std::error::Error for InvalidAddressLiteral {}
+
+#[derive(Debug)]
+pub struct InvalidMailbox(());
+
+impl fmt::Display for InvalidMailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid local part")
+    }
+}
+
+impl std::error::Error for InvalidMailbox {}
+
+#[derive(Debug)]
+pub struct InvalidNotifyConditions(());
+
+impl fmt::Display for InvalidNotifyConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "notify conditions are empty or contain duplicates")
+    }
+}
+
+impl std::error::Error for InvalidNotifyConditions {}
+
+#[derive(Debug)]
+pub struct InvalidOrcpt(());
+
+impl fmt::Display for InvalidOrcpt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "address type is not an atom or address cannot be encoded"
+        )
+    }
+}
+
+impl std::error::Error for InvalidOrcpt {}
+
 #[derive(Debug)]
 pub struct InvalidTextString(());
 
@@ -849,35 +1850,44 @@ fn is_text_string_byte(byte: u8) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{Capability, ReplyCode, Response, TextString};
+    use std::{
+        collections::HashSet,
+        net::{Ipv4Addr, Ipv6Addr},
+    };
+
+    use super::{
+        AddressLiteral, AtomOrQuoted, Capability, Command, Domain, DomainOrAddress, ForwardPath,
+        GeneralAddressLiteral, Mailbox, Notify, NotifyConditions, NotifyOn, Orcpt, Parameter, Path,
+        ReplyCode, Response, ReversePath, TextString,
+    };
 
     #[test]
     fn test_serialize_greeting() {
         let tests = &[
             (
                 Response::Greeting {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap().into(),
                     text: "".into(),
                 },
                 b"220 example.org\r\n".as_ref(),
             ),
             (
                 Response::Greeting {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap().into(),
                     text: "A".into(),
                 },
                 b"220 example.org A\r\n".as_ref(),
             ),
             (
                 Response::Greeting {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap().into(),
                     text: "A\nB".into(),
                 },
                 b"220-example.org A\r\n220 B\r\n".as_ref(),
             ),
             (
                 Response::Greeting {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap().into(),
                     text: "A\nB\nC".into(),
                 },
                 b"220-example.org A\r\n220-B\r\n220 C\r\n".as_ref(),
@@ -891,12 +1901,166 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_serialize_u_label() {
+        // HELO, EHLO, and their responses are sent before SMTPUTF8 is negotiated.
+        let domain = Domain::new("bücher.example").unwrap();
+
+        let mut out = Vec::new();
+        Command::Ehlo {
+            domain_or_address: domain.clone().into(),
+        }
+        .serialize(&mut out)
+        .unwrap();
+        assert_eq!(out, b"EHLO xn--bcher-kva.example\r\n");
+
+        let mut out = Vec::new();
+        Command::Helo {
+            domain_or_address: domain.clone().into(),
+        }
+        .serialize(&mut out)
+        .unwrap();
+        assert_eq!(out, b"HELO xn--bcher-kva.example\r\n");
+
+        let mut out = Vec::new();
+        Response::greeting(domain.clone(), "Hi")
+            .serialize(&mut out)
+            .unwrap();
+        assert_eq!(out, b"220 xn--bcher-kva.example Hi\r\n");
+
+        let mut out = Vec::new();
+        Response::ehlo(domain, None::<String>, vec![])
+            .serialize(&mut out)
+            .unwrap();
+        assert_eq!(out, b"250 xn--bcher-kva.example\r\n");
+    }
+
+    #[test]
+    fn test_serialize_path_to_ascii() {
+        let domain = Domain::new("bücher.example").unwrap();
+        let mailbox = Mailbox::new("john", domain.clone().into()).unwrap();
+        let path = Path {
+            source_route: vec![domain.clone()],
+            mailbox: mailbox.clone(),
+        };
+
+        let command = Command::Mail {
+            reverse_path: ReversePath::Path(path.clone()).to_ascii(),
+            parameters: vec![],
+        };
+        assert!(!command.requires_smtputf8());
+        let mut out = Vec::new();
+        command.serialize(&mut out).unwrap();
+        assert_eq!(
+            out,
+            b"MAIL FROM:<@xn--bcher-kva.example:john@xn--bcher-kva.example>\r\n"
+        );
+
+        let tests: [(ForwardPath, &[u8]); 3] = [
+            (
+                ForwardPath::Path(mailbox.into()),
+                b"RCPT TO:<john@xn--bcher-kva.example>\r\n",
+            ),
+            (
+                ForwardPath::PostmasterAt(domain.clone()),
+                b"RCPT TO:<Postmaster@xn--bcher-kva.example>\r\n",
+            ),
+            (ForwardPath::Postmaster, b"RCPT TO:<Postmaster>\r\n"),
+        ];
+
+        for (forward_path, expected) in tests {
+            let command = Command::Rcpt {
+                forward_path: forward_path.to_ascii(),
+                parameters: vec![],
+            };
+            assert!(!command.requires_smtputf8());
+            let mut out = Vec::new();
+            command.serialize(&mut out).unwrap();
+            assert_eq!(out, expected);
+        }
+
+        // The local part is not converted.
+        let mailbox = Mailbox::new("jöhn", domain.into()).unwrap().to_ascii();
+        assert_eq!(mailbox.to_string(), "jöhn@xn--bcher-kva.example");
+        assert!(mailbox.requires_smtputf8());
+    }
+
+    #[test]
+    fn test_requires_smtputf8() {
+        let domain = Domain::new("bücher.example").unwrap();
+        let mailbox = Mailbox::new("john", Domain::new("example.com").unwrap().into()).unwrap();
+
+        let tests = [
+            (
+                Command::Ehlo {
+                    domain_or_address: domain.clone().into(),
+                },
+                false,
+            ),
+            (
+                Command::Helo {
+                    domain_or_address: domain.clone().into(),
+                },
+                false,
+            ),
+            (
+                Command::Mail {
+                    reverse_path: ReversePath::Path(mailbox.clone().into()),
+                    parameters: vec![Parameter::Size(1)],
+                },
+                false,
+            ),
+            (
+                Command::Mail {
+                    reverse_path: ReversePath::Null,
+                    parameters: vec![Parameter::SmtpUtf8],
+                },
+                true,
+            ),
+            (
+                Command::Rcpt {
+                    forward_path: ForwardPath::PostmasterAt(domain),
+                    parameters: vec![],
+                },
+                true,
+            ),
+            (
+                Command::Rcpt {
+                    forward_path: ForwardPath::Path(mailbox.into()),
+                    parameters: vec![Parameter::Other {
+                        keyword: "X-NAME".into(),
+                        value: Some("jöhn".into()),
+                    }],
+                },
+                true,
+            ),
+            (
+                Command::Noop {
+                    argument: Some(AtomOrQuoted::Atom("jöhn".into())),
+                },
+                true,
+            ),
+            (Command::Noop { argument: None }, false),
+            (
+                Command::Bdat {
+                    size: 1,
+                    last: true,
+                },
+                false,
+            ),
+        ];
+
+        for (command, expected) in tests {
+            assert_eq!(command.requires_smtputf8(), expected, "{:?}", command);
+        }
+    }
+
     #[test]
     fn test_serialize_ehlo() {
         let tests = &[
             (
                 Response::Ehlo {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap(),
                     greet: None,
                     capabilities: vec![],
                 },
@@ -904,7 +2068,7 @@ mod tests {
             ),
             (
                 Response::Ehlo {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap(),
                     greet: Some("...".into()),
                     capabilities: vec![],
                 },
@@ -912,7 +2076,7 @@ mod tests {
             ),
             (
                 Response::Ehlo {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap(),
                     greet: Some("...".into()),
                     capabilities: vec![Capability::StartTLS],
                 },
@@ -920,7 +2084,7 @@ mod tests {
             ),
             (
                 Response::Ehlo {
-                    domain: "example.org".into(),
+                    domain: Domain::new("example.org").unwrap(),
                     greet: Some("...".into()),
                     capabilities: vec![Capability::StartTLS, Capability::Size(12345)],
                 },
@@ -967,4 +2131,286 @@ mod tests {
             assert_eq!(expected, &got);
         }
     }
+
+    #[test]
+    fn test_domain() {
+        let label = "a".repeat(63);
+        let long = [label.as_str(); 4].join(".");
+        let too_long = format!("{}.a", long);
+
+        for valid in ["example.com", "a", "1-2.x", label.as_str(), long.as_str()] {
+            assert!(Domain::new(valid).is_ok(), "{}", valid);
+        }
+
+        let too_long_label = "a".repeat(64);
+        for invalid in [
+            "",
+            "a b",
+            "a\r\n",
+            "a..b",
+            "-a",
+            "a-",
+            "a.",
+            too_long_label.as_str(),
+            too_long.as_str(),
+        ] {
+            assert!(Domain::new(invalid).is_err(), "{}", invalid);
+        }
+
+        let lower = Domain::new("example.com").unwrap();
+        let upper = Domain::new("EXAMPLE.Com").unwrap();
+        assert_eq!(lower, upper);
+        assert_eq!(upper.to_string(), "EXAMPLE.Com");
+
+        let set: HashSet<_> = [lower, upper].into_iter().collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_domain_idna() {
+        let u_label = Domain::new("bücher.example").unwrap();
+        let a_label = Domain::new("XN--BCHER-KVA.example").unwrap();
+
+        assert!(!u_label.is_ascii());
+        assert!(a_label.is_ascii());
+        assert_eq!(u_label, a_label);
+        assert_eq!(u_label.to_ascii().as_str(), "xn--bcher-kva.example");
+        assert_eq!(a_label.to_unicode().as_str(), "bücher.example");
+        assert_eq!(u_label.to_unicode().as_str(), "bücher.example");
+        assert_eq!(a_label.to_ascii().as_str(), "XN--BCHER-KVA.example");
+
+        let set: HashSet<_> = [u_label, a_label, Domain::new("BÜCHER.example").unwrap()]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 1);
+
+        assert_eq!(
+            Domain::new("BÜCHER.example").unwrap().as_str(),
+            "bücher.example"
+        );
+
+        let ascii = Domain::new("Example.COM").unwrap();
+        assert_eq!(ascii.to_unicode().as_str(), "Example.COM");
+
+        assert_eq!(
+            Domain::new("例え.テスト").unwrap().to_ascii().as_str(),
+            "xn--r8jz45g.xn--zckzah"
+        );
+
+        for invalid in ["bü cher.example", "bücher..example", "-bücher.example"] {
+            assert!(Domain::new(invalid).is_err(), "{}", invalid);
+        }
+
+        let long = format!("{}.example", "ü".repeat(60));
+        assert!(Domain::new(long).is_err());
+    }
+
+    #[test]
+    fn test_mailbox() {
+        let mailbox = Mailbox::new(
+            "a@b",
+            DomainOrAddress::Domain(Domain::new("example.com").unwrap()),
+        )
+        .unwrap();
+        assert_eq!(mailbox.to_string(), "\"a@b\"@example.com");
+
+        let mailbox =
+            Mailbox::new("", DomainOrAddress::Address(Ipv6Addr::LOCALHOST.into())).unwrap();
+        assert_eq!(mailbox.to_string(), "\"\"@[IPv6:::1]");
+
+        let mailbox = Mailbox::new("jöhn", Domain::new("bücher.example").unwrap().into()).unwrap();
+        assert_eq!(mailbox.to_string(), "jöhn@bücher.example");
+        assert!(mailbox.requires_smtputf8());
+
+        let mailbox = Mailbox::new("jöhn döe", Domain::new("example.com").unwrap().into()).unwrap();
+        assert_eq!(mailbox.to_string(), "\"jöhn döe\"@example.com");
+        assert!(mailbox.requires_smtputf8());
+
+        let domain = Domain::new("example.com").unwrap();
+        assert!(Mailbox::new("a\u{85}b", domain.into()).is_err());
+
+        let general = GeneralAddressLiteral::new("x-tag", "content").unwrap();
+        let mailbox = Mailbox::new("foo", AddressLiteral::General(general).into()).unwrap();
+        assert_eq!(mailbox.local_part(), "foo");
+        assert_eq!(mailbox.to_string(), "foo@[x-tag:content]");
+
+        assert!(Mailbox::new(
+            "f\r\noo",
+            DomainOrAddress::Domain(Domain::new("example.com").unwrap())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_address_literal() {
+        let tests = [
+            (
+                AddressLiteral::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                "[192.0.2.1]",
+            ),
+            (
+                AddressLiteral::V6("2001:DB8:0:0:0:0:0:1".parse().unwrap()),
+                "[IPv6:2001:db8::1]",
+            ),
+            (
+                AddressLiteral::V6("::FFFF:192.0.2.1".parse().unwrap()),
+                "[IPv6:::ffff:192.0.2.1]",
+            ),
+            (
+                AddressLiteral::General(GeneralAddressLiteral::new("x-tag", "content").unwrap()),
+                "[x-tag:content]",
+            ),
+        ];
+
+        for (test, expected) in tests {
+            let mut got = Vec::new();
+            test.serialize(&mut got).unwrap();
+            assert_eq!(got, expected.as_bytes());
+        }
+
+        for (tag, content) in [
+            ("IPv6", "content"),
+            ("x-tag", "con]tent"),
+            ("x-tag", "con\r\ntent"),
+            ("x-tag", ""),
+            ("x.tag", "content"),
+            ("", "content"),
+        ] {
+            assert!(GeneralAddressLiteral::new(tag, content).is_err());
+        }
+
+        let address = AddressLiteral::from(Ipv4Addr::LOCALHOST);
+        assert_eq!(address.ip(), Some(Ipv4Addr::LOCALHOST.into()));
+    }
+
+    #[test]
+    fn test_serialize_null_reverse_path() {
+        let command = Command::Mail {
+            reverse_path: ReversePath::Null,
+            parameters: vec![],
+        };
+        let mut out = Vec::new();
+        command.serialize(&mut out).unwrap();
+        assert_eq!(out, b"MAIL FROM:<>\r\n");
+
+        // A path with an empty local part is not the null reverse-path.
+        let command = Command::Mail {
+            reverse_path: ReversePath::Path(Path::from(
+                Mailbox::new(
+                    "",
+                    DomainOrAddress::Domain(Domain::new("example.com").unwrap()),
+                )
+                .unwrap(),
+            )),
+            parameters: vec![],
+        };
+        let mut out = Vec::new();
+        command.serialize(&mut out).unwrap();
+        assert_eq!(out, b"MAIL FROM:<\"\"@example.com>\r\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_reverse_path() {
+        let null = ReversePath::Null;
+        let path = ReversePath::Path(Path::from(
+            Mailbox::new(
+                "",
+                DomainOrAddress::Domain(Domain::new("example.com").unwrap()),
+            )
+            .unwrap(),
+        ));
+
+        let serialized = serde_json::to_string(&null).unwrap();
+        assert_eq!(serialized, "\"Null\"");
+        assert_ne!(serde_json::to_string(&path).unwrap(), serialized);
+
+        for reverse_path in [null, path] {
+            let command = Command::Mail {
+                reverse_path,
+                parameters: vec![],
+            };
+            let serialized = serde_json::to_string(&command).unwrap();
+            let deserialized: Command = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, command);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_mailbox() {
+        let mailbox = Mailbox::new(
+            "a b",
+            AddressLiteral::General(GeneralAddressLiteral::new("x-tag", "content").unwrap()).into(),
+        )
+        .unwrap();
+        let serialized = serde_json::to_string(&mailbox).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Mailbox>(&serialized).unwrap(),
+            mailbox
+        );
+
+        let injected = serialized.replace("a b", "a\\r\\nRSET");
+        assert_ne!(injected, serialized);
+        assert!(serde_json::from_str::<Mailbox>(&injected).is_err());
+
+        let injected = serialized.replace("content", "con]tent");
+        assert_ne!(injected, serialized);
+        assert!(serde_json::from_str::<Mailbox>(&injected).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_domain() {
+        let domain: Domain = serde_json::from_str("\"example.com\"").unwrap();
+        assert_eq!(domain, Domain::new("example.com").unwrap());
+        assert!(serde_json::from_str::<Domain>("\"a b\"").is_err());
+    }
+
+    #[test]
+    fn test_dsn_parameters() {
+        assert!(NotifyConditions::new(vec![NotifyOn::Success, NotifyOn::Delay]).is_ok());
+        assert!(NotifyConditions::new(vec![]).is_err());
+        assert!(NotifyConditions::new(vec![NotifyOn::Delay, NotifyOn::Delay]).is_err());
+
+        let mut out = Vec::new();
+        Parameter::Notify(Notify::On(
+            NotifyConditions::new(vec![NotifyOn::Failure, NotifyOn::Success]).unwrap(),
+        ))
+        .serialize(&mut out)
+        .unwrap();
+        assert_eq!(out, b"NOTIFY=FAILURE,SUCCESS");
+
+        let orcpt = Orcpt::new("rfc822", "bob@example.com").unwrap();
+        assert_eq!(orcpt.addr_type(), "rfc822");
+        assert_eq!(orcpt.address(), "bob@example.com");
+
+        for addr_type in ["", "rfc 822", "rfc822;x", "rfc822\r\nRSET", "utf-8\u{e9}"] {
+            assert!(Orcpt::new(addr_type, "bob@example.com").is_err());
+        }
+
+        assert!(Orcpt::new("utf-8", "").is_err());
+        assert!(Orcpt::new("UTF-8", "bob\0@example.com").is_err());
+        assert!(Orcpt::new("rfc822", "bob\0@example.com").is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_dsn_parameters() {
+        let notify: Notify = serde_json::from_str(r#"{"On":["Success"]}"#).unwrap();
+        assert_eq!(
+            notify,
+            Notify::On(NotifyConditions::new(vec![NotifyOn::Success]).unwrap())
+        );
+        assert!(serde_json::from_str::<Notify>(r#"{"On":[]}"#).is_err());
+        assert!(serde_json::from_str::<Notify>(r#"{"On":["Delay","Delay"]}"#).is_err());
+
+        let orcpt = Orcpt::new("rfc822", "bob@example.com").unwrap();
+        let serialized = serde_json::to_string(&orcpt).unwrap();
+        assert_eq!(serde_json::from_str::<Orcpt>(&serialized).unwrap(), orcpt);
+        assert!(serde_json::from_str::<Orcpt>(
+            r#"{"addr_type":"rfc822;x","address":"bob@example.com"}"#
+        )
+        .is_err());
+    }
 }
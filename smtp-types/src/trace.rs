@@ -0,0 +1,669 @@
+//! Trace Information (RFC 5321, Section 4.4)
+
+use std::{
+    fmt,
+    io::{self, Write},
+    net::IpAddr,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    imf::{DateTime, InvalidDateTime, MessageId},
+    utils::{is_atom, is_quoted_content, write_folded},
+    AddressLiteral, AtomOrQuoted, Command, Domain, DomainOrAddress, Mailbox, Path, ReversePath,
+};
+
+/// Return-path-line = "Return-Path:" FWS Reverse-path <CRLF>
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReturnPath {
+    /// The null reverse-path "<>", e.g., used for delivery status notifications.
+    Null,
+    Path(Path),
+}
+
+impl ReturnPath {
+    /// Create a Return-Path from the reverse-path of a `MAIL` command.
+    ///
+    /// Returns `None` when `command` is not a `MAIL` command.
+    pub fn from_command(command: &Command) -> Option<ReturnPath> {
+        match command {
+            Command::Mail { reverse_path, .. } => Some(ReturnPath::from(reverse_path)),
+            _ => None,
+        }
+    }
+
+    /// Serialize as "Return-Path:" header field (including the trailing CRLF).
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            ReturnPath::Null => writer.write_all(b"Return-Path: <>\r\n"),
+            ReturnPath::Path(path) => write!(writer, "Return-Path: <{}>\r\n", path),
+        }
+    }
+}
+
+impl From<&ReversePath> for ReturnPath {
+    fn from(reverse_path: &ReversePath) -> Self {
+        match reverse_path {
+            ReversePath::Null => ReturnPath::Null,
+            ReversePath::Path(path) => ReturnPath::Path(path.clone()),
+        }
+    }
+}
+
+/// "Delivered-To:" header field added by a delivery agent to detect mail loops.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeliveredTo {
+    /// Recipient of the delivery, e.g., "user@example.com".
+    pub recipient: Mailbox,
+}
+
+impl DeliveredTo {
+    /// Serialize as "Delivered-To:" header field (including the trailing CRLF).
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        write!(writer, "Delivered-To: {}\r\n", self.recipient)
+    }
+}
+
+/// A "Received:" time stamp line.
+///
+/// Stamp = From-domain By-domain Opt-info [CFWS] ";" FWS date-time
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Received {
+    /// Identity of the SMTP client as presented in EHLO/HELO (plus TCP-info).
+    pub from: ExtendedDomain,
+    /// Identity of the SMTP server that added this stamp (plus TCP-info).
+    pub by: ExtendedDomain,
+    /// Link = "TCP" / Addtl-Link
+    pub via: Option<String>,
+    /// Protocol = "ESMTP" / "SMTP" / Attdl-Protocol
+    pub with: Option<Protocol>,
+    pub id: Option<ReceivedId>,
+    /// Path or Mailbox (a Mailbox is stored as a Path without source route).
+    pub for_path: Option<Path>,
+    pub additional_clauses: Vec<AdditionalClause>,
+    pub date_time: DateTime,
+}
+
+impl Received {
+    /// Start building a stamp for a message received from `client` (as presented in EHLO/HELO)
+    /// connected from `peer`. `host` is the name of the receiving host.
+    pub fn builder<H>(client: DomainOrAddress, peer: IpAddr, host: H) -> ReceivedBuilder
+    where
+        H: Into<String>,
+    {
+        ReceivedBuilder {
+            client,
+            peer,
+            peer_name: None,
+            host: host.into(),
+            protocol: None,
+            id: None,
+            recipient: None,
+        }
+    }
+
+    /// Check that the clauses are well-formed, e.g., that they contain no line breaks.
+    ///
+    /// Stamps created by [`ReceivedBuilder`] are always valid.
+    pub fn validate(&self) -> Result<(), InvalidReceived> {
+        if let Some(ref via) = self.via {
+            check(is_atom(via), "via")?;
+        }
+
+        if let Some(ref with) = self.with {
+            check(is_atom(with.name()), "protocol")?;
+        }
+
+        if let Some(ReceivedId::Atom(ref id)) = self.id {
+            check(is_atom(id), "id")?;
+        }
+
+        for clause in &self.additional_clauses {
+            let is_valid_value = match &clause.value {
+                AtomOrQuoted::Atom(atom) => is_atom(atom),
+                AtomOrQuoted::Quoted(quoted) => is_quoted_content(quoted),
+            };
+
+            check(is_atom(&clause.name) && is_valid_value, "additional clause")?;
+        }
+
+        Ok(())
+    }
+
+    /// Serialize as "Received:" header field (including the trailing CRLF).
+    ///
+    /// The field is folded at clause boundaries to keep lines within 78 characters.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] when the stamp is invalid, see
+    /// [`Received::validate`].
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        self.validate().map_err(invalid_input)?;
+
+        let mut units = Vec::new();
+
+        // From-domain = "FROM" FWS Extended-Domain
+        // By-domain = CFWS "BY" FWS Extended-Domain
+        //
+        // Domains are written as A-labels.
+        for (keyword, extended_domain) in [("from", &self.from), ("by", &self.by)] {
+            match extended_domain {
+                ExtendedDomain::Domain(domain) => {
+                    units.push(format!("{} {}", keyword, domain.to_ascii()))
+                }
+                ExtendedDomain::DomainWithTcpInfo { domain, tcp_info } => {
+                    units.push(format!("{} {}", keyword, domain.to_ascii()));
+                    units.push(format!("({})", tcp_info));
+                }
+                ExtendedDomain::AddressWithTcpInfo { address, tcp_info } => {
+                    units.push(format!("{} [{}]", keyword, address));
+                    units.push(format!("({})", tcp_info));
+                }
+            }
+        }
+
+        // Opt-info = [Via] [With] [ID] [For] [Additional-Registered-Clauses]
+        if let Some(via) = &self.via {
+            units.push(format!("via {}", via));
+        }
+
+        if let Some(with) = &self.with {
+            units.push(format!("with {}", with.name()));
+        }
+
+        if let Some(id) = &self.id {
+            let mut value = Vec::new();
+            id.serialize(&mut value)?;
+            units.push(format!("id {}", String::from_utf8_lossy(&value)));
+        }
+
+        if let Some(for_path) = &self.for_path {
+            units.push(format!("for <{}>", for_path));
+        }
+
+        for clause in &self.additional_clauses {
+            let mut value = Vec::new();
+            clause.value.serialize(&mut value)?;
+            units.push(format!(
+                "{} {}",
+                clause.name,
+                String::from_utf8_lossy(&value)
+            ));
+        }
+
+        // Opt-info [CFWS] ";" FWS date-time
+        if let Some(last) = units.last_mut() {
+            last.push(';');
+        }
+
+        let mut date_time = Vec::new();
+        self.date_time.serialize(&mut date_time)?;
+        units.push(String::from_utf8_lossy(&date_time).into_owned());
+
+        write_folded(writer, "Received", &units, 78)
+    }
+}
+
+/// Builder for a [`Received`] stamp from connection data.
+///
+/// See [`Received::builder`].
+#[derive(Clone, Debug)]
+pub struct ReceivedBuilder {
+    client: DomainOrAddress,
+    peer: IpAddr,
+    peer_name: Option<String>,
+    host: String,
+    protocol: Option<Protocol>,
+    id: Option<String>,
+    recipient: Option<Path>,
+}
+
+impl ReceivedBuilder {
+    /// Name of the peer as found by a reverse lookup of its address.
+    pub fn peer_name<N: Into<String>>(mut self, peer_name: N) -> Self {
+        self.peer_name = Some(peer_name.into());
+        self
+    }
+
+    /// Protocol used to receive the message, e.g., [`Protocol::ESMTPS`].
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Queue ID assigned to the message.
+    pub fn id<I: Into<String>>(mut self, id: I) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Recipient of the message, i.e., a [`Mailbox`] or a [`Path`].
+    ///
+    /// RFC 5321 only allows a "for" clause when there is exactly one recipient. Don't
+    /// call this when the message has more than one recipient.
+    pub fn recipient<R: Into<Path>>(mut self, recipient: R) -> Self {
+        self.recipient = Some(recipient.into());
+        self
+    }
+
+    /// Build the stamp. The receiving host is recorded with its name only.
+    ///
+    /// Fails when the peer name or the host is not a valid domain, or when the protocol name or
+    /// the ID is not an atom.
+    pub fn build(self, date_time: DateTime) -> Result<Received, InvalidReceived> {
+        let reverse_name = match self.peer_name {
+            Some(peer_name) => {
+                Some(Domain::new(peer_name).map_err(|_| InvalidReceived { field: "peer name" })?)
+            }
+            None => None,
+        };
+
+        let tcp_info = TcpInfo {
+            reverse_name,
+            address: self.peer.into(),
+        };
+
+        let from = match self.client {
+            DomainOrAddress::Domain(domain) => {
+                ExtendedDomain::DomainWithTcpInfo { domain, tcp_info }
+            }
+            DomainOrAddress::Address(address) => {
+                ExtendedDomain::AddressWithTcpInfo { address, tcp_info }
+            }
+        };
+
+        let host = Domain::new(self.host).map_err(|_| InvalidReceived { field: "host" })?;
+
+        if let Some(ref protocol) = self.protocol {
+            check(is_atom(protocol.name()), "protocol")?;
+        }
+
+        if let Some(ref id) = self.id {
+            check(is_atom(id), "id")?;
+        }
+
+        Ok(Received {
+            from,
+            by: ExtendedDomain::Domain(host),
+            via: None,
+            with: self.protocol,
+            id: self.id.map(ReceivedId::Atom),
+            for_path: self.recipient,
+            additional_clauses: vec![],
+            date_time,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidReceived {
+    field: &'static str,
+}
+
+impl fmt::Display for InvalidReceived {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {} in Received stamp", self.field)
+    }
+}
+
+impl std::error::Error for InvalidReceived {}
+
+fn check(valid: bool, field: &'static str) -> Result<(), InvalidReceived> {
+    match valid {
+        true => Ok(()),
+        false => Err(InvalidReceived { field }),
+    }
+}
+
+fn invalid_input<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+/// Extended-Domain = Domain /
+///                   ( Domain FWS "(" TCP-info ")" ) /
+///                   ( address-literal FWS "(" TCP-info ")" )
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExtendedDomain {
+    Domain(Domain),
+    DomainWithTcpInfo {
+        domain: Domain,
+        tcp_info: TcpInfo,
+    },
+    AddressWithTcpInfo {
+        address: AddressLiteral,
+        tcp_info: TcpInfo,
+    },
+}
+
+impl ExtendedDomain {
+    /// Domain or address as presented by the client (without TCP-info).
+    pub fn domain_or_address(&self) -> DomainOrAddress {
+        match self {
+            ExtendedDomain::Domain(domain) | ExtendedDomain::DomainWithTcpInfo { domain, .. } => {
+                DomainOrAddress::Domain(domain.clone())
+            }
+            ExtendedDomain::AddressWithTcpInfo { address, .. } => {
+                DomainOrAddress::Address(address.clone())
+            }
+        }
+    }
+
+    /// Information derived from the TCP connection (if any).
+    pub fn tcp_info(&self) -> Option<&TcpInfo> {
+        match self {
+            ExtendedDomain::Domain(_) => None,
+            ExtendedDomain::DomainWithTcpInfo { tcp_info, .. }
+            | ExtendedDomain::AddressWithTcpInfo { tcp_info, .. } => Some(tcp_info),
+        }
+    }
+}
+
+/// ID = CFWS "ID" FWS ( Atom / msg-id )
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReceivedId {
+    /// Atom, e.g., a queue ID.
+    Atom(String),
+    MsgId(MessageId),
+}
+
+impl ReceivedId {
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            ReceivedId::Atom(atom) => writer.write_all(atom.as_bytes()),
+            ReceivedId::MsgId(message_id) => message_id.serialize(writer),
+        }
+    }
+}
+
+/// Information derived by server from TCP connection not client EHLO.
+///
+/// TCP-info = address-literal / ( Domain FWS address-literal )
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TcpInfo {
+    /// Result of the reverse lookup of `address` (if any).
+    pub reverse_name: Option<Domain>,
+    pub address: AddressLiteral,
+}
+
+impl TcpInfo {
+    /// IP address of the connection (if `address` is an IPv4 or IPv6 address literal).
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.address.ip()
+    }
+}
+
+impl fmt::Display for TcpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reverse_name {
+            Some(reverse_name) => write!(f, "{} [{}]", reverse_name.to_ascii(), self.address),
+            None => write!(f, "[{}]", self.address),
+        }
+    }
+}
+
+/// Mail Transmission Types registered with IANA, see RFC 3848 and RFC 6531.
+///
+/// The suffixes encode how the message was received: "A" means that the client was
+/// authenticated (RFC 4954), "S" means that the transport was secured by STARTTLS (RFC 3207).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Protocol {
+    /// SMTP [RFC5321]
+    SMTP,
+    /// SMTP with Service Extensions [RFC5321]
+    ESMTP,
+    /// ESMTP with SMTP AUTH [RFC3848]
+    ESMTPA,
+    /// ESMTP with STARTTLS [RFC3848]
+    ESMTPS,
+    /// ESMTP with both STARTTLS and SMTP AUTH [RFC3848]
+    ESMTPSA,
+    /// LMTP [RFC2033]
+    LMTP,
+    /// LMTP with LMTP AUTH [RFC3848]
+    LMTPA,
+    /// LMTP with STARTTLS [RFC3848]
+    LMTPS,
+    /// LMTP with both STARTTLS and LMTP AUTH [RFC3848]
+    LMTPSA,
+    /// UTF8 SMTP [RFC6531]
+    UTF8SMTP,
+    /// UTF8 SMTP with SMTP AUTH [RFC6531]
+    UTF8SMTPA,
+    /// UTF8 SMTP with STARTTLS [RFC6531]
+    UTF8SMTPS,
+    /// UTF8 SMTP with both STARTTLS and SMTP AUTH [RFC6531]
+    UTF8SMTPSA,
+    /// UTF8 LMTP [RFC6531]
+    UTF8LMTP,
+    /// UTF8 LMTP with LMTP AUTH [RFC6531]
+    UTF8LMTPA,
+    /// UTF8 LMTP with STARTTLS [RFC6531]
+    UTF8LMTPS,
+    /// UTF8 LMTP with both STARTTLS and LMTP AUTH [RFC6531]
+    UTF8LMTPSA,
+    /// Other
+    Other(String),
+}
+
+impl Protocol {
+    pub fn name(&self) -> &str {
+        match self {
+            Protocol::SMTP => "SMTP",
+            Protocol::ESMTP => "ESMTP",
+            Protocol::ESMTPA => "ESMTPA",
+            Protocol::ESMTPS => "ESMTPS",
+            Protocol::ESMTPSA => "ESMTPSA",
+            Protocol::LMTP => "LMTP",
+            Protocol::LMTPA => "LMTPA",
+            Protocol::LMTPS => "LMTPS",
+            Protocol::LMTPSA => "LMTPSA",
+            Protocol::UTF8SMTP => "UTF8SMTP",
+            Protocol::UTF8SMTPA => "UTF8SMTPA",
+            Protocol::UTF8SMTPS => "UTF8SMTPS",
+            Protocol::UTF8SMTPSA => "UTF8SMTPSA",
+            Protocol::UTF8LMTP => "UTF8LMTP",
+            Protocol::UTF8LMTPA => "UTF8LMTPA",
+            Protocol::UTF8LMTPS => "UTF8LMTPS",
+            Protocol::UTF8LMTPSA => "UTF8LMTPSA",
+            Protocol::Other(other) => other,
+        }
+    }
+
+    /// Was the client authenticated?
+    ///
+    /// Returns `false` for unknown protocols.
+    pub fn is_authenticated(&self) -> bool {
+        matches!(
+            self,
+            Protocol::ESMTPA
+                | Protocol::ESMTPSA
+                | Protocol::LMTPA
+                | Protocol::LMTPSA
+                | Protocol::UTF8SMTPA
+                | Protocol::UTF8SMTPSA
+                | Protocol::UTF8LMTPA
+                | Protocol::UTF8LMTPSA
+        )
+    }
+
+    /// Was the transport secured by STARTTLS?
+    ///
+    /// Returns `false` for unknown protocols.
+    pub fn is_encrypted(&self) -> bool {
+        matches!(
+            self,
+            Protocol::ESMTPS
+                | Protocol::ESMTPSA
+                | Protocol::LMTPS
+                | Protocol::LMTPSA
+                | Protocol::UTF8SMTPS
+                | Protocol::UTF8SMTPSA
+                | Protocol::UTF8LMTPS
+                | Protocol::UTF8LMTPSA
+        )
+    }
+
+    /// Was the message transmitted with SMTPUTF8?
+    pub fn is_utf8(&self) -> bool {
+        matches!(
+            self,
+            Protocol::UTF8SMTP
+                | Protocol::UTF8SMTPA
+                | Protocol::UTF8SMTPS
+                | Protocol::UTF8SMTPSA
+                | Protocol::UTF8LMTP
+                | Protocol::UTF8LMTPA
+                | Protocol::UTF8LMTPS
+                | Protocol::UTF8LMTPSA
+        )
+    }
+
+    pub fn serialize(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(self.name().as_bytes())
+    }
+}
+
+impl From<&str> for Protocol {
+    /// Protocol names are case-insensitive.
+    fn from(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "SMTP" => Protocol::SMTP,
+            "ESMTP" => Protocol::ESMTP,
+            "ESMTPA" => Protocol::ESMTPA,
+            "ESMTPS" => Protocol::ESMTPS,
+            "ESMTPSA" => Protocol::ESMTPSA,
+            "LMTP" => Protocol::LMTP,
+            "LMTPA" => Protocol::LMTPA,
+            "LMTPS" => Protocol::LMTPS,
+            "LMTPSA" => Protocol::LMTPSA,
+            "UTF8SMTP" => Protocol::UTF8SMTP,
+            "UTF8SMTPA" => Protocol::UTF8SMTPA,
+            "UTF8SMTPS" => Protocol::UTF8SMTPS,
+            "UTF8SMTPSA" => Protocol::UTF8SMTPSA,
+            "UTF8LMTP" => Protocol::UTF8LMTP,
+            "UTF8LMTPA" => Protocol::UTF8LMTPA,
+            "UTF8LMTPS" => Protocol::UTF8LMTPS,
+            "UTF8LMTPSA" => Protocol::UTF8LMTPSA,
+            _ => Protocol::Other(value.to_owned()),
+        }
+    }
+}
+
+/// Additional-Registered-Clauses = CFWS Atom FWS String
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdditionalClause {
+    pub name: String,
+    pub value: AtomOrQuoted,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A single hop of a "Received:" chain.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hop {
+    /// Sending host as presented in EHLO/HELO.
+    pub from: DomainOrAddress,
+    /// Receiving host, i.e., the host that added the stamp.
+    pub by: DomainOrAddress,
+    /// IP address of the sending host as recorded in TCP-info.
+    pub ip: Option<IpAddr>,
+    pub protocol: Option<Protocol>,
+    /// Was the hop secured by STARTTLS (according to `protocol`)?
+    pub tls: bool,
+    /// Was the sending host authenticated (according to `protocol`)?
+    pub authenticated: bool,
+    /// Seconds between the previous (older) hop and this hop.
+    ///
+    /// This is `None` for the first hop or when the previous stamp is malformed. It may be
+    /// negative when the clocks of the involved hosts are skewed.
+    pub delay: Option<i64>,
+    pub received: Received,
+}
+
+impl From<Received> for Hop {
+    fn from(received: Received) -> Self {
+        let protocol = received.with.clone();
+
+        Hop {
+            from: received.from.domain_or_address(),
+            by: received.by.domain_or_address(),
+            ip: received.from.tcp_info().and_then(TcpInfo::ip),
+            tls: protocol
+                .as_ref()
+                .map(Protocol::is_encrypted)
+                .unwrap_or(false),
+            authenticated: protocol
+                .as_ref()
+                .map(Protocol::is_authenticated)
+                .unwrap_or(false),
+            protocol,
+            delay: None,
+            received,
+        }
+    }
+}
+
+/// A "Received:" header field that could not be parsed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MalformedStamp {
+    /// The raw header field (including the field name and folding).
+    pub raw: Vec<u8>,
+    pub error: StampError,
+}
+
+impl fmt::Display for MalformedStamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "malformed stamp ({}): {:?}",
+            self.error,
+            String::from_utf8_lossy(&self.raw).trim_end()
+        )
+    }
+}
+
+impl std::error::Error for MalformedStamp {}
+
+/// Reason why a stamp could not be parsed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StampError {
+    /// The stamp does not match the RFC 5321 grammar.
+    Syntax,
+    /// The date-time uses an obsolete form of RFC 5322, e.g., a two-digit year.
+    ///
+    /// RFC 5321 prohibits these forms in trace fields.
+    ObsoleteDateTime,
+    /// The date-time is syntactically valid, but not a valid calendar date and time.
+    InvalidDateTime(InvalidDateTime),
+}
+
+impl fmt::Display for StampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StampError::Syntax => write!(f, "syntax error"),
+            StampError::ObsoleteDateTime => {
+                write!(
+                    f,
+                    "obsolete date-time syntax (e.g. two-digit year) is prohibited"
+                )
+            }
+            StampError::InvalidDateTime(error) => write!(f, "invalid date-time: {}", error),
+        }
+    }
+}
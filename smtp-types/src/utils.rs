@@ -1,6 +1,16 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    io::Write,
+    net::{Ipv4Addr, Ipv6Addr},
+};
 
-pub(crate) fn escape_quoted(unescaped: &str) -> Cow<str> {
+use crate::{AddressLiteral, GeneralAddressLiteral};
+
+/// Escape the content of a Quoted-string.
+///
+/// Only DQUOTE and backslash are escaped. All other characters, e.g., SP, are valid qtext and are
+/// written literally, i.e., the result is the shortest escaping that is unescaped to `unescaped`.
+pub(crate) fn escape_quoted(unescaped: &str) -> Cow<'_, str> {
     let mut escaped = Cow::Borrowed(unescaped);
 
     if escaped.contains('\\') {
@@ -13,3 +23,133 @@ pub(crate) fn escape_quoted(unescaped: &str) -> Cow<str> {
 
     escaped
 }
+
+/// Write a header field with the given `name` and `units` of its body, separated by single
+/// spaces. A unit is moved to a continuation line if the current line would otherwise exceed
+/// `limit` characters (excluding CRLF). Units are never split, thus, long units may still
+/// exceed the limit.
+pub(crate) fn write_folded<S: AsRef<str>>(
+    writer: &mut impl Write,
+    name: &str,
+    units: &[S],
+    limit: usize,
+) -> std::io::Result<()> {
+    writer.write_all(name.as_bytes())?;
+    writer.write_all(b":")?;
+
+    let mut line_length = name.len() + 1;
+    let mut line_is_empty = true;
+
+    for unit in units {
+        let unit = unit.as_ref();
+
+        if !line_is_empty && line_length + 1 + unit.len() > limit {
+            writer.write_all(b"\r\n\t")?;
+            writer.write_all(unit.as_bytes())?;
+            line_length = 1 + unit.len();
+        } else {
+            writer.write_all(b" ")?;
+            writer.write_all(unit.as_bytes())?;
+            line_length += 1 + unit.len();
+        }
+
+        line_is_empty = false;
+    }
+
+    writer.write_all(b"\r\n")
+}
+
+/// Domain = sub-domain *("." sub-domain)
+///
+/// A label must not be longer than 63 octets and a domain not longer than 255 octets.
+pub(crate) fn is_domain(value: &str) -> bool {
+    value.len() <= 255
+        && value.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// dcontent = %d33-90 / %d94-126
+pub(crate) fn is_address(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| matches!(b, 33..=90 | 94..=126))
+}
+
+/// Atom = 1*atext
+pub(crate) fn is_atom(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-/=?^_`{|}~".contains(&b))
+}
+
+/// field-name = 1*ftext
+///
+/// ftext = %d33-57 / %d59-126  ; Printable US-ASCII characters not including ":"
+pub(crate) fn is_field_name(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| matches!(b, 33..=57 | 59..=126))
+}
+
+/// Can `value` be written as the content of a Quoted-string (after escaping)?
+///
+/// QcontentSMTP = qtextSMTP / quoted-pairSMTP
+/// qtextSMTP =/ UTF8-non-ascii  ; RFC 6531
+pub(crate) fn is_quoted_content(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| matches!(c, ' '..='~') || (!c.is_ascii() && !c.is_control()))
+}
+
+/// Atom = 1*atext
+///
+/// atext =/ UTF8-non-ascii  ; RFC 6531
+pub(crate) fn is_utf8_atom(value: &str) -> bool {
+    !value.is_empty()
+        && value.chars().all(|c| match c.is_ascii() {
+            true => c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c),
+            false => !c.is_control(),
+        })
+}
+
+/// Parse an address literal without brackets, e.g., "192.0.2.1" or "IPv6:2001:db8::1".
+///
+/// address-literal = "[" ( IPv4-address-literal /
+///                   IPv6-address-literal /
+///                   General-address-literal ) "]"
+pub(crate) fn parse_address_literal(value: &str) -> Option<AddressLiteral> {
+    match value.split_once(':') {
+        // IPv6-address-literal = "IPv6:" IPv6-addr
+        Some((tag, address)) if tag.eq_ignore_ascii_case("IPv6") => {
+            address.parse::<Ipv6Addr>().ok().map(AddressLiteral::V6)
+        }
+        Some((tag, content)) => GeneralAddressLiteral::new(tag, content)
+            .ok()
+            .map(AddressLiteral::General),
+        // IPv4-address-literal = Snum 3("."  Snum)
+        None => {
+            let snums = value
+                .split('.')
+                .map(|snum| match (1..=3).contains(&snum.len()) {
+                    true => snum.parse::<u8>().ok(),
+                    false => None,
+                })
+                .collect::<Option<Vec<u8>>>()?;
+
+            match snums[..] {
+                [a, b, c, d] => Some(AddressLiteral::V4(Ipv4Addr::new(a, b, c, d))),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// General-address-literal = Standardized-tag ":" 1*dcontent
+///
+/// Standardized-tag = Ldh-str
+pub(crate) fn is_general_address_literal(tag: &str, content: &str) -> bool {
+    is_domain(tag) && !tag.contains('.') && !tag.eq_ignore_ascii_case("IPv6") && is_address(content)
+}
@@ -0,0 +1,275 @@
+//! xtext (RFC 3461) and unitext (RFC 6533)
+
+use std::{borrow::Cow, fmt};
+
+/// xchar = any ASCII CHAR between "!" (33) and "~" (126) inclusive, except for "+" and "=".
+pub fn is_xchar(byte: u8) -> bool {
+    matches!(byte, 33..=126) && byte != b'+' && byte != b'='
+}
+
+/// QCHAR = %x21-2a / %x2c-3c / %x3e-5b / %x5d-7e
+///
+/// Printable ASCII except "+", "=", and "\".
+pub fn is_qchar(byte: u8) -> bool {
+    is_xchar(byte) && byte != b'\\'
+}
+
+/// Encode `value` as xtext.
+///
+/// xtext = *( xchar / hexchar )
+///
+/// Every octet that is not a xchar is encoded as hexchar, i.e., "+" followed by two upper case
+/// hex digits.
+pub fn encode(value: &str) -> Cow<'_, str> {
+    if value.bytes().all(is_xchar) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut encoded = String::with_capacity(value.len() + 8);
+
+    for byte in value.bytes() {
+        if is_xchar(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("+{:02X}", byte));
+        }
+    }
+
+    Cow::Owned(encoded)
+}
+
+/// Decode xtext.
+///
+/// Fails when `xtext` contains a character that is neither a xchar nor part of a hexchar, when
+/// a hexchar is not followed by exactly two upper case hex digits, or when the decoded octets are
+/// not valid UTF-8.
+pub fn decode(xtext: &str) -> Result<String, InvalidXtext> {
+    let mut decoded = Vec::with_capacity(xtext.len());
+    let mut bytes = xtext.bytes();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'+' => {
+                let high = bytes
+                    .next()
+                    .and_then(upper_hex_value)
+                    .ok_or(InvalidXtext(()))?;
+                let low = bytes
+                    .next()
+                    .and_then(upper_hex_value)
+                    .ok_or(InvalidXtext(()))?;
+                decoded.push(high << 4 | low);
+            }
+            _ if is_xchar(byte) => decoded.push(byte),
+            _ => return Err(InvalidXtext(())),
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| InvalidXtext(()))
+}
+
+/// Encode an (internationalized) address as utf-8-addr-xtext, i.e., using ASCII only.
+///
+/// utf-8-addr-xtext = 1*(QCHAR / EmbeddedUnicodeChar)
+///
+/// Every character that is not a QCHAR is encoded as EmbeddedUnicodeChar, e.g., "ü" as
+/// "\x{FC}". The result is also valid xtext and may be used in "ORCPT" even when SMTPUTF8 was
+/// not negotiated.
+///
+/// Fails when `address` is empty or contains NUL, which cannot be represented.
+pub fn encode_utf8_addr_xtext(address: &str) -> Result<String, InvalidXtext> {
+    encode_utf8_addr(address, false)
+}
+
+/// Encode an (internationalized) address as utf-8-addr-unitext.
+///
+/// utf-8-addr-unitext = 1*(QUCHAR / EmbeddedUnicodeChar)
+///
+/// Like [`encode_utf8_addr_xtext`], but non-ASCII characters are kept as is. The result may only
+/// be used when SMTPUTF8 was negotiated.
+pub fn encode_utf8_addr_unitext(address: &str) -> Result<String, InvalidXtext> {
+    encode_utf8_addr(address, true)
+}
+
+fn encode_utf8_addr(address: &str, unitext: bool) -> Result<String, InvalidXtext> {
+    if address.is_empty() || address.contains('\0') {
+        return Err(InvalidXtext(()));
+    }
+
+    let mut encoded = String::with_capacity(address.len());
+
+    for character in address.chars() {
+        if (character.is_ascii() && is_qchar(character as u8)) || (unitext && !character.is_ascii())
+        {
+            encoded.push(character);
+        } else {
+            encoded.push_str(&format!("\\x{{{:02X}}}", u32::from(character)));
+        }
+    }
+
+    Ok(encoded)
+}
+
+/// Decode utf-8-addr-xtext or utf-8-addr-unitext.
+///
+/// EmbeddedUnicodeChar = %x5C.78 "{" HEXPOINT "}"
+///
+/// The HEXPOINT must consist of 2 to 6 upper case hex digits without superfluous leading zeros
+/// and must denote a Unicode scalar value. ASCII characters must only be encoded when they are not
+/// a QCHAR. Fails when `value` is empty or contains a character that is neither a QCHAR, a
+/// non-ASCII character, nor part of an EmbeddedUnicodeChar.
+pub fn decode_utf8_addr(value: &str) -> Result<String, InvalidXtext> {
+    if value.is_empty() {
+        return Err(InvalidXtext(()));
+    }
+
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(character) = rest.chars().next() {
+        if character == '\\' {
+            let hexpoint = rest
+                .strip_prefix("\\x{")
+                .and_then(|tail| tail.split_once('}'))
+                .map(|(hexpoint, tail)| {
+                    rest = tail;
+                    hexpoint
+                })
+                .ok_or(InvalidXtext(()))?;
+
+            decoded.push(decode_hexpoint(hexpoint).ok_or(InvalidXtext(()))?);
+        } else if !character.is_ascii() || is_qchar(character as u8) {
+            decoded.push(character);
+            rest = &rest[character.len_utf8()..];
+        } else {
+            return Err(InvalidXtext(()));
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// HEXPOINT, e.g., "FC" in "\x{FC}".
+fn decode_hexpoint(hexpoint: &str) -> Option<char> {
+    let is_canonical = (2..=6).contains(&hexpoint.len())
+        && (hexpoint.len() == 2 || !hexpoint.starts_with('0'))
+        && hexpoint.bytes().all(|byte| upper_hex_value(byte).is_some());
+
+    if !is_canonical {
+        return None;
+    }
+
+    let character = char::from_u32(u32::from_str_radix(hexpoint, 16).ok()?)?;
+
+    match character {
+        '\0' => None,
+        _ if character.is_ascii() && is_qchar(character as u8) => None,
+        _ => Some(character),
+    }
+}
+
+fn upper_hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidXtext(());
+
+impl fmt::Display for InvalidXtext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid xtext or unitext")
+    }
+}
+
+impl std::error::Error for InvalidXtext {}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode, decode_utf8_addr, encode, encode_utf8_addr_unitext, encode_utf8_addr_xtext,
+    };
+
+    #[test]
+    fn test_xtext() {
+        let tests = [
+            ("", ""),
+            ("bob@example.com", "bob@example.com"),
+            ("Bob Smith", "Bob+20Smith"),
+            ("a+b=c", "a+2Bb+3Dc"),
+            ("\r\n", "+0D+0A"),
+            ("ü", "+C3+BC"),
+        ];
+
+        for (value, expected) in tests {
+            assert_eq!(encode(value), expected);
+            assert_eq!(decode(expected).unwrap(), value);
+        }
+
+        let tests = [
+            "+", "+2", "+2b", "+g0", "a=b", "a b", "a\tb", "+C3", "ü", "+2B+",
+        ];
+
+        for test in tests {
+            assert!(decode(test).is_err(), "{:?}", test);
+        }
+    }
+
+    #[test]
+    fn test_utf8_addr() {
+        let tests = [
+            ("bob@example.com", "bob@example.com", "bob@example.com"),
+            (
+                "用户@例子.广告",
+                "\\x{7528}\\x{6237}@\\x{4F8B}\\x{5B50}.\\x{5E7F}\\x{544A}",
+                "用户@例子.广告",
+            ),
+            (
+                "a+b=c\\d",
+                "a\\x{2B}b\\x{3D}c\\x{5C}d",
+                "a\\x{2B}b\\x{3D}c\\x{5C}d",
+            ),
+            ("é 😀", "\\x{E9}\\x{20}\\x{1F600}", "é\\x{20}😀"),
+        ];
+
+        for (address, xtext, unitext) in tests {
+            assert_eq!(encode_utf8_addr_xtext(address).unwrap(), xtext);
+            assert_eq!(encode_utf8_addr_unitext(address).unwrap(), unitext);
+            assert_eq!(decode_utf8_addr(xtext).unwrap(), address);
+            assert_eq!(decode_utf8_addr(unitext).unwrap(), address);
+            // utf-8-addr-xtext is also valid xtext.
+            assert_eq!(decode(xtext).unwrap(), xtext);
+        }
+
+        let tests = [
+            "",
+            "a+b",
+            "a=b",
+            "a b",
+            "\\",
+            "\\x",
+            "\\x{}",
+            "\\x{E9",
+            "\\x{e9}",
+            "\\x{0E9}",
+            "\\x{41}",
+            "\\x{00}",
+            "\\x{D800}",
+            "\\x{110000}",
+            "\\x{1234567}",
+            "\\y{E9}",
+        ];
+
+        for test in tests {
+            assert!(decode_utf8_addr(test).is_err(), "{:?}", test);
+        }
+
+        for address in ["", "a\0b"] {
+            assert!(encode_utf8_addr_xtext(address).is_err());
+            assert!(encode_utf8_addr_unitext(address).is_err());
+        }
+    }
+}